@@ -1,10 +1,20 @@
-use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use serde::Serialize;
-use source_map_parser::{stack_transform, SourceMapParserClient};
+use source_map_parser::{
+  code_frame::CodeFrameOptions,
+  context_window::{ContextOptions, HighlightSpan},
+  function_name::FunctionNameSource,
+  generated_code::GeneratedExcerpt,
+  position::ColumnBase,
+  source_provider::{GitSourceRoot, LocalSourceRoot, SourceProvider},
+  stack_transform::{self, ErrorStack, FrameInfo, StackEngine},
+  token_generator::SourceCode,
+  FrameResult, LookupBias, LookupOptions, MappedErrorStack, SourceMapParserClient,
+};
 use std::{
-  collections::HashMap,
+  collections::{HashMap, HashSet},
   fs,
-  io::{self, Read, Write},
+  io::{self, IsTerminal, Read, Write},
   path::{Path, PathBuf},
 };
 
@@ -42,12 +52,24 @@ enum Command {
     after_long_help = LOOKUP_AFTER_HELP
   )]
   Lookup(LookupArgs),
+  #[command(
+    about = "反查原始 file:line:column -> 编译后位置",
+    long_about = "反查原始位置对应的全部编译后位置。",
+    after_long_help = REVERSE_AFTER_HELP
+  )]
+  Reverse(ReverseArgs),
   #[command(
     about = "从 .map 提取内嵌 sourcesContent",
     long_about = "提取 .map 内嵌 sourcesContent。",
     after_long_help = UNPACK_AFTER_HELP
   )]
   Unpack(UnpackArgs),
+  #[command(
+    about = "合成多级 .map 链（如 TS -> Babel -> Terser）为单个 .map",
+    long_about = "按构建顺序合成多级 .map，输出展平后的单个 .map。",
+    after_long_help = COMPOSE_AFTER_HELP
+  )]
+  Compose(ComposeArgs),
 }
 const MAP_ABOUT: &str = "映射整段 error stack。stack 从 --stack 文件或 stdin(-) 读入。";
 const MAP_AFTER_HELP: &str = r#"SOURCEMAP 来源（三选一，互斥）:
@@ -57,24 +79,126 @@ const MAP_AFTER_HELP: &str = r#"SOURCEMAP 来源（三选一，互斥）:
                                  例: "{url}.map" -> https://x/app.js 取 https://x/app.js.map
                                  仅做字符串替换，不 eval（不接受任意脚本 rule）
 
+WASM:
+        --wasm <FILE>           本地 .wasm 模块，读取其 sourceMappingURL 自定义段（相对路径按模块所在目录解析）
+        --wasm-map <FILE>       直接指定 wasm 帧的 .map
+                                wasm 帧（wasm-function[N]:0x偏移）以字节偏移为列号查询；
+                                两者都未给出时与 JS 帧一样按上面的 SOURCEMAP 来源查找
+
+LOOKUP:
+        --bias <BIAS>           glb（默认，取之前最近的 token）| lub（取同行之后最近的）| nearest（同行列距最近）
+        --max-column-distance <N>
+                                命中 token 与帧列号的列距超过 N（或不在同一行）时记为失败
+                                column_distance 为该列距，不在同一行时省略
+        --column-base <0|1>     帧列号与输出原始列号的基准，默认 1（V8 / Firefox / Safari 堆栈列号为 1-based）；
+                                wasm 帧的字节偏移始终按 0-based 处理。列号单位为 UTF-16 码元
+
+CONTEXT:
+        --context <N>           每帧前后各取 N 行原始源码上下文
+        --context-before <N>    / --context-after <N> 分别指定前后行数（覆盖 --context）
+        --max-line-width <N>    单行超过 N 个 UTF-16 码元时围绕目标列截取，省略部分以 … 标记；
+                                被截断的行带 column_offset（保留部分在原行的起始列），span 换算到截断后的文本
+        --generated <FILE|auto> 附加编译后 JS 文件，每帧输出 generated：查询位置附近的编译后代码摘录；
+                                auto 取 map 的 file 字段，相对 map 所在目录 / URL 解析，找不到时提示并跳过；
+                                给出 FILE 时所有帧须对应同一个 map，否则报错（多个 map 请用 auto）。
+                                目标行过长（压缩代码）时截取查询列附近的代码按语句拆行缩进（beautified: true），
+                                前后行数与行宽同样按上面的参数
+                                map 缺少 sourcesContent 时 --context 以编译后代码摘录回退（未给出 --generated 时按 auto 查找），
+                                此时不输出 context，仅输出 generated
+
+SOURCES:
+        --source-root <DIR>     map 缺少 sourcesContent 时按 sources[i] 从本地源码目录读取原始源码，
+                                仅在给出 --context 时读取（上下文、代码帧使用）；
+                                去掉 webpack:// 等协议头与 ?query 后作为相对 DIR 的路径，越出 DIR 的 .. 视为读取不到
+        --source-base <DIR>     sources 相对 --source-root 下的该子目录解析（通常为 map 所在目录），
+                                例: --source-base dist/assets 时 ../../src/a.js 读取 <root>/src/a.js
+        --git-repo <PATH>       同上，但从本地 git 仓库的指定提交读取（不检出，不受工作区改动影响），
+                                路径相对仓库根，越出仓库根的 .. 视为读取不到；与 --source-root 二选一
+        --git-rev <REV>         构建所用的提交 / tag / 分支，默认 HEAD
+        --source-prefix <FROM=TO>
+                                sources 路径前缀改写（--source-root 与 --git-repo 均适用），可重复，按顺序取第一个匹配项，
+                                例: --source-prefix webpack:///./src=./src
+                                读取不到的 source 仍视为缺失（可由 --generated 回退）
+
+STACK FORMAT:
+        --format <FMT>          v8 | firefox | safari | node | hermes | auto（默认）
+                                显式指定时只按该引擎语法解析，不做跨引擎回退；
+                                auto 逐行兼容各引擎，识别出的引擎写入输出 engine 字段
+
 OUTPUT (JSON):
-    { "success": [{ "raw", "source", "line", "column", "name", "context"? }],
-      "fail":    [{ "raw", "reason" }] }
+    { "engine"?: "v8" | "firefox" | "safari" | "node" | "hermes",
+      "success": [{ "raw", "source", "line", "column", "name", "name_source"?, "column_distance"?, "context"?, "span"?, "generated"?, "frame" }],
+      "fail":    [{ "raw", "reason", "frame" }] }
+    name 优先取所在原始函数名（name_source: function_map | source_scan | call_site，
+    function_map 来自 Hermes / Metro map 的 x_facebook_sources），
+    其次取映射 token 的 names 条目，均无时回退为压缩后的帧名
+    reason 为失败原因：native frame / eval frame / frame has no position，或查询失败的具体原因
+    （行号超出编译后文件、该列之前无映射等）；source 缺少 sourcesContent 不算失败，仅不输出 context
+    cause（[cause]: / Caused by:）与 AggregateError 子错误（[errors]: [...]）的帧按出现顺序一并列出，
+    各自独立映射
+    --text 时改为输出还原后的堆栈文本：帧格式与输入一致（V8 `at fn (src:line:col)` /
+    Firefox `fn@src:line:col`），消息行与未能映射的帧原样保留，顺序不变；
+    cause 以 "Caused by:" 分段，子错误在 [errors]: [ ... ] 中缩进输出
+    span 为目标行上映射表达式的范围 { start, end }（0-based UTF-16 列，左闭右开），随 context 输出；
+    --text 与 --context 同时给出时，每个还原帧下方附带代码帧（行号栏 + ^ 标出 span），
+    颜色由 --color auto|always|never 控制
+    generated 为 { line, column, beautified, lines: [{ line, is_target, code }], span? }，
+    --text 时在原始代码帧之后以 "generated:" 附上编译后代码帧
 
 EXAMPLES:
     # 本地单 map
     source-map-parser map --stack err.txt --map app.js.map --pretty
     # stdin + 目录匹配 + 取 3 行上下文
     cat err.txt | source-map-parser map --stack - --map-dir ./maps --context 3
+    # 输出可直接贴进工单的文本堆栈
+    source-map-parser map --stack err.txt --map app.js.map --text
+    # 文本堆栈 + 每帧 2 行上下文的代码帧
+    source-map-parser map --stack err.txt --map app.js.map --text --context 2
+    # map 已裁掉 sourcesContent, 从本地检出读取源码
+    source-map-parser map --stack err.txt --map app.js.map --context 2 --source-root ./repo --source-prefix webpack:///./=./
+    # 从发布所用 tag 读取源码
+    source-map-parser map --stack err.txt --map app.js.map --context 2 --git-repo ./repo --git-rev v1.4.2
+    # 同时对照压缩后的 bundle
+    source-map-parser map --stack err.txt --map app.js.map --generated app.js --text --context 2
     # 按模板远程拉 map
     source-map-parser map --stack - --map-url-template "{url}.map" < err.txt
 "#;
-const LOOKUP_AFTER_HELP: &str = r#"OUTPUT (JSON):
-    { "source", "line", "column", "name", "context"? }
+const LOOKUP_AFTER_HELP: &str = r#"OPTIONS:
+        --bias <BIAS>               glb（默认）| lub | nearest，见 map --help
+        --max-column-distance <N>   命中 token 列距超过 N 时报错
+        --column-base <0|1>         --column 与输出 column 的基准，默认 0（与 source map 一致）
+        --context <N>               前后各取 N 行上下文；--context-before / --context-after 分别指定
+        --max-line-width <N>        上下文单行最大宽度，见 map --help
+        --source-root <DIR>         本地源码目录，补全缺失的 sourcesContent；--source-prefix FROM=TO 改写路径前缀，见 map --help
+        --git-repo <PATH>           同上，从 git 仓库 --git-rev <REV>（默认 HEAD）的提交读取，见 map --help
+        --generated <FILE|auto>     附加编译后 JS 文件，输出 generated 编译后代码摘录，见 map --help；
+                                    缺少 sourcesContent 时 --context 以其回退
+
+OUTPUT (JSON):
+    { "source", "line", "column", "name", "column_distance"?, "context"?, "span"?, "generated"? }
 
 EXAMPLE:
     source-map-parser lookup --map app.js.map --line 1 --column 24680 --context 5
 "#;
+const COMPOSE_AFTER_HELP: &str = r#"OPTIONS:
+        --map <FILE>        可重复，按构建顺序给出（第一项为最先执行的转换）
+
+OUTPUT:
+    合成后的 source map JSON（sourcesContent 取自第一项）
+
+EXAMPLE:
+    source-map-parser compose --map app.ts.map --map app.babel.map --map app.min.js.map -o app.min.js.composed.map
+"#;
+const REVERSE_AFTER_HELP: &str = r#"OPTIONS:
+        --source <PATH>     原始源码路径，可只给路径后缀（如 src/a.ts 匹配 webpack:///./src/a.ts）
+        --column <N>        可选；该列无映射时取同行之后最近的列，不传则返回整行
+
+OUTPUT (JSON):
+    [{ "line", "column" }]
+
+EXAMPLE:
+    source-map-parser reverse --map app.js.map --source src/App.tsx --line 42 --column 8
+"#;
 const UNPACK_AFTER_HELP: &str = r#"OPTIONS:
         --list              只列出 sources 路径，不落盘
         --out-dir <DIR>     按原始路径结构还原源码到目录
@@ -94,11 +218,24 @@ struct MapArgs {
   map_dir: Option<PathBuf>,
   #[arg(long, conflicts_with_all = ["map", "map_dir"], value_name = "TPL", help = "由 JS URL 推 .map URL 的模板，含占位符 {url}；仅做字符串替换，不 eval")]
   map_url_template: Option<String>,
+  #[arg(
+    long,
+    value_name = "FILE",
+    help = "本地 .wasm 模块，wasm 帧按其 sourceMappingURL 自定义段取 map"
+  )]
+  wasm: Option<PathBuf>,
+  #[arg(
+    long,
+    conflicts_with = "wasm",
+    value_name = "FILE",
+    help = "wasm 帧使用的 .map 文件（优先于 --wasm）"
+  )]
+  wasm_map: Option<PathBuf>,
   #[arg(
     long,
     default_value = "auto",
     value_enum,
-    help = "堆栈格式，默认 auto（自动识别 V8/Firefox/Safari）"
+    help = "堆栈格式，默认 auto（自动识别并在输出 engine 字段中报告）"
   )]
   format: StackFormat,
   #[arg(
@@ -107,17 +244,236 @@ struct MapArgs {
     help = "每帧附带原始源码上下文行数，默认 0（不取）"
   )]
   context: u32,
+  #[command(flatten)]
+  context_args: ContextArgs,
+  #[command(flatten)]
+  source_args: SourceArgs,
   #[arg(
     long,
     help = "禁止任何网络请求（--map-url-template 时改为报错而非下载）"
   )]
   no_fetch: bool,
+  #[arg(long, help = "输出还原后的堆栈文本（保持输入格式与顺序）而非 JSON")]
+  text: bool,
+  #[arg(
+    long,
+    value_name = "FILE|auto",
+    help = "附加编译后 JS 文件，每帧附带编译后代码摘录；auto 按 map 的 file 字段相对 map 位置查找"
+  )]
+  generated: Option<String>,
+  #[arg(
+    long,
+    default_value = "auto",
+    value_enum,
+    value_name = "WHEN",
+    help = "--text --context 时代码帧的 ANSI 颜色：auto（输出到终端且未设 NO_COLOR 时启用）| always | never"
+  )]
+  color: ColorWhen,
+  #[command(flatten)]
+  lookup: LookupOptionArgs,
+}
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum ColorWhen {
+  Auto,
+  Always,
+  Never,
+}
+impl ColorWhen {
+  fn enabled(self, cli: &Cli) -> bool {
+    match self {
+      ColorWhen::Always => true,
+      ColorWhen::Never => false,
+      ColorWhen::Auto => {
+        cli.output.is_none() && io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+      }
+    }
+  }
+}
+#[derive(Args, Debug)]
+struct ContextArgs {
+  #[arg(
+    long,
+    value_name = "N",
+    help = "目标行之前的上下文行数（覆盖 --context）"
+  )]
+  context_before: Option<u32>,
+  #[arg(
+    long,
+    value_name = "N",
+    help = "目标行之后的上下文行数（覆盖 --context）"
+  )]
+  context_after: Option<u32>,
+  #[arg(
+    long,
+    value_name = "N",
+    value_parser = clap::value_parser!(u32).range(1..),
+    help = "上下文单行最大宽度（UTF-16 码元），超出时围绕目标列截取，省略部分以 … 标记"
+  )]
+  max_line_width: Option<u32>,
+}
+impl ContextArgs {
+  /// `context` 为 --context 的值; 三者均未给出时不取上下文
+  fn options(&self, context: Option<u32>) -> Option<ContextOptions> {
+    if context.is_none() && self.context_before.is_none() && self.context_after.is_none() {
+      return None;
+    }
+    let lines = context.unwrap_or(0);
+    Some(ContextOptions {
+      before: self.context_before.unwrap_or(lines),
+      after: self.context_after.unwrap_or(lines),
+      max_line_width: self.max_line_width,
+    })
+  }
+}
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("source_provider").args(["source_root", "git_repo"])))]
+struct SourceArgs {
+  #[arg(
+    long,
+    value_name = "DIR",
+    help = "本地源码目录：map 缺少 sourcesContent 时按 sources 路径从该目录读取原始源码"
+  )]
+  source_root: Option<PathBuf>,
+  #[arg(
+    long,
+    value_name = "DIR",
+    requires = "source_root",
+    help = "sources 相对 --source-root 下的该子目录解析（通常为 map 所在目录），.. 最多回到 --source-root"
+  )]
+  source_base: Option<PathBuf>,
+  #[arg(
+    long,
+    value_name = "PATH",
+    help = "本地 git 仓库：map 缺少 sourcesContent 时从 --git-rev 指定的提交读取原始源码（不检出）"
+  )]
+  git_repo: Option<PathBuf>,
+  #[arg(
+    long,
+    value_name = "REV",
+    requires = "git_repo",
+    help = "构建所用的提交、tag 或分支，默认 HEAD"
+  )]
+  git_rev: Option<String>,
+  #[arg(
+    long,
+    value_name = "FROM=TO",
+    requires = "source_provider",
+    help = "sources 路径前缀改写，可重复，按顺序取第一个匹配项，如 webpack:///./src=./src"
+  )]
+  source_prefix: Vec<String>,
+}
+impl SourceArgs {
+  fn provider(&self) -> Result<Option<Box<dyn SourceProvider>>, CliError> {
+    let mut rewrites = Vec::with_capacity(self.source_prefix.len());
+    for rewrite in &self.source_prefix {
+      let (from, to) = rewrite.split_once('=').ok_or_else(|| {
+        CliError::Msg(format!(
+          "invalid --source-prefix {rewrite}, expected FROM=TO"
+        ))
+      })?;
+      rewrites.push((from, to));
+    }
+    if let Some(repo) = &self.git_repo {
+      let rev = self.git_rev.as_deref().unwrap_or("HEAD");
+      let provider = rewrites
+        .into_iter()
+        .fold(GitSourceRoot::open(repo, rev)?, |p, (from, to)| {
+          p.with_prefix_rewrite(from, to)
+        });
+      return Ok(Some(Box::new(provider)));
+    }
+    Ok(self.source_root.as_ref().map(|root| {
+      let mut local = LocalSourceRoot::new(root);
+      if let Some(base) = &self.source_base {
+        local = local.with_base_dir(base);
+      }
+      let provider = rewrites
+        .into_iter()
+        .fold(local, |p, (from, to)| p.with_prefix_rewrite(from, to));
+      Box::new(provider) as Box<dyn SourceProvider>
+    }))
+  }
+}
+#[derive(Args, Debug)]
+struct LookupOptionArgs {
+  #[arg(
+    long,
+    default_value = "glb",
+    value_enum,
+    help = "列无精确映射时的取 token 方向，默认 glb"
+  )]
+  bias: Bias,
+  #[arg(
+    long,
+    value_name = "N",
+    help = "命中 token 与查询列的最大列距，超出视为失败"
+  )]
+  max_column_distance: Option<u32>,
+  #[arg(
+    long,
+    value_enum,
+    value_name = "0|1",
+    help = "输入编译后列号与输出原始列号的基准；map 默认 1（浏览器堆栈），lookup 默认 0（source map）"
+  )]
+  column_base: Option<ColumnBaseArg>,
+}
+impl LookupOptionArgs {
+  /// `default_base` 为未指定 --column-base 时的列号基准
+  fn options(&self, default_base: ColumnBase) -> LookupOptions {
+    let column_base = match self.column_base {
+      Some(ColumnBaseArg::Zero) => ColumnBase::Zero,
+      Some(ColumnBaseArg::One) => ColumnBase::One,
+      None => default_base,
+    };
+    LookupOptions {
+      // map 的帧列号与输出列号共用同一基准
+      column_base,
+      frame_column_base: column_base,
+      bias: match self.bias {
+        Bias::Glb => LookupBias::GreatestLowerBound,
+        Bias::Lub => LookupBias::LeastUpperBound,
+        Bias::Nearest => LookupBias::Nearest,
+      },
+      max_column_distance: self.max_column_distance,
+      ..Default::default()
+    }
+  }
+}
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum ColumnBaseArg {
+  #[value(name = "0")]
+  Zero,
+  #[value(name = "1")]
+  One,
+}
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum Bias {
+  Glb,
+  Lub,
+  Nearest,
 }
 #[derive(Copy, Clone, Debug, ValueEnum)]
 enum StackFormat {
   V8,
+  Firefox,
+  Safari,
+  Node,
+  Hermes,
   Auto,
 }
+impl StackFormat {
+  /// 显式指定的引擎, auto 时为 None
+  fn engine(self) -> Option<StackEngine> {
+    match self {
+      StackFormat::V8 => Some(StackEngine::V8),
+      StackFormat::Firefox => Some(StackEngine::Firefox),
+      StackFormat::Safari => Some(StackEngine::Safari),
+      StackFormat::Node => Some(StackEngine::Node),
+      StackFormat::Hermes => Some(StackEngine::Hermes),
+      StackFormat::Auto => None,
+    }
+  }
+}
 #[derive(Args, Debug)]
 struct LookupArgs {
   #[arg(long)]
@@ -128,6 +484,29 @@ struct LookupArgs {
   column: u32,
   #[arg(long)]
   context: Option<u32>,
+  #[command(flatten)]
+  context_args: ContextArgs,
+  #[command(flatten)]
+  source_args: SourceArgs,
+  #[arg(
+    long,
+    value_name = "FILE|auto",
+    help = "附加编译后 JS 文件，输出附带编译后代码摘录；auto 按 map 的 file 字段相对 --map 查找"
+  )]
+  generated: Option<String>,
+  #[command(flatten)]
+  lookup: LookupOptionArgs,
+}
+#[derive(Args, Debug)]
+struct ReverseArgs {
+  #[arg(long)]
+  map: PathBuf,
+  #[arg(long)]
+  source: String,
+  #[arg(long)]
+  line: u32,
+  #[arg(long)]
+  column: Option<u32>,
 }
 #[derive(Args, Debug)]
 struct UnpackArgs {
@@ -139,8 +518,16 @@ struct UnpackArgs {
   list: bool,
 }
 
+#[derive(Args, Debug)]
+struct ComposeArgs {
+  #[arg(long = "map", required = true, value_name = "FILE")]
+  maps: Vec<PathBuf>,
+}
+
 #[derive(Serialize)]
 struct MapOutput {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  engine: Option<StackEngine>,
   success: Vec<MappedFrame>,
   fail: Vec<FailedFrame>,
 }
@@ -152,12 +539,22 @@ struct MappedFrame {
   column: u32,
   name: String,
   #[serde(skip_serializing_if = "Option::is_none")]
-  context: Option<serde_json::Value>,
+  name_source: Option<FunctionNameSource>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  column_distance: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  context: Option<Vec<SourceCode>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  span: Option<HighlightSpan>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  generated: Option<GeneratedExcerpt>,
+  frame: FrameInfo,
 }
 #[derive(Serialize)]
 struct FailedFrame {
   raw: String,
   reason: String,
+  frame: FrameInfo,
 }
 #[derive(Serialize)]
 struct LookupOutput {
@@ -166,7 +563,13 @@ struct LookupOutput {
   column: u32,
   name: Option<String>,
   #[serde(skip_serializing_if = "Option::is_none")]
+  column_distance: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   context: Option<serde_json::Value>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  span: Option<HighlightSpan>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  generated: Option<GeneratedExcerpt>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -179,6 +582,8 @@ enum CliError {
   Json(#[from] serde_json::Error),
   #[error(transparent)]
   Client(#[from] source_map_parser::ClientError),
+  #[error(transparent)]
+  Lookup(#[from] source_map_parser::LookupError),
 }
 
 fn main() {
@@ -195,7 +600,9 @@ fn run(cli: &Cli) -> Result<i32, CliError> {
   match &cli.command {
     Command::Map(a) => run_map(cli, a),
     Command::Lookup(a) => run_lookup(cli, a),
+    Command::Reverse(a) => run_reverse(cli, a),
     Command::Unpack(a) => run_unpack(cli, a),
+    Command::Compose(a) => run_compose(cli, a),
   }
 }
 
@@ -226,74 +633,139 @@ fn write_json<T: Serialize>(cli: &Cli, value: &T) -> Result<(), CliError> {
   Ok(())
 }
 
+fn write_text(cli: &Cli, text: &str) -> Result<(), CliError> {
+  if let Some(p) = &cli.output {
+    fs::write(p, format!("{text}\n"))?
+  } else {
+    println!("{text}");
+  }
+  Ok(())
+}
+
 fn run_map(cli: &Cli, a: &MapArgs) -> Result<i32, CliError> {
   let stack = read_input(&a.stack)?;
-  let frames = stack_transform::parse_stack_trace(&stack);
+  let (engine, es) = match a.format.engine() {
+    Some(e) => (Some(e), ErrorStack::from_raw_as(&stack, e)),
+    None => (
+      stack_transform::detect_engine(&stack),
+      ErrorStack::from_raw(&stack),
+    ),
+  };
+  let frames = es.all_stacks();
   if frames.is_empty() {
     return Ok(3);
   }
+  let wasm_key = wasm_map_key(a)?;
+  let options = a.lookup.options(ColumnBase::One);
+  let context = a.context_args.options((a.context > 0).then_some(a.context));
+  // 源码只用于上下文与代码帧, 未要求时不读取
+  let provider = match context {
+    Some(_) => a.source_args.provider()?,
+    None => None,
+  };
+  // 先确定每个可映射帧的 map, (帧文件, 是否 wasm 帧) -> map key
+  let mut keys: HashMap<(&str, bool), String> = HashMap::new();
+  for f in frames.iter().filter(|f| f.is_mappable()) {
+    let is_wasm = f.wasm_function.is_some();
+    if keys.contains_key(&(f.source_file, is_wasm)) {
+      continue;
+    }
+    let key = match (&wasm_key, is_wasm) {
+      (Some(k), true) => k.clone(),
+      _ => map_key(a, f.source_file)?,
+    };
+    keys.insert((f.source_file, is_wasm), key);
+  }
+  // 显式给出的编译后文件只对应一个 map, 帧分属多个 map 时无法判断归属
+  if a.generated.as_deref().is_some_and(|g| g != "auto") {
+    let js_maps: HashSet<&String> = keys.iter().filter(|(k, _)| !k.1).map(|(_, v)| v).collect();
+    if js_maps.len() > 1 {
+      return Err(CliError::Msg(
+        "--generated <FILE> requires all frames to resolve to one map; use --generated auto".into(),
+      ));
+    }
+  }
+  // 同一 map 只加载一次
+  let mut clients: HashMap<String, SourceMapParserClient> = HashMap::new();
+  for f in frames.iter().filter(|f| f.is_mappable()) {
+    let is_wasm = f.wasm_function.is_some();
+    let key = &keys[&(f.source_file, is_wasm)];
+    if clients.contains_key(key) {
+      continue;
+    }
+    let bytes = load_map(key, a.no_fetch)?;
+    let mut client = SourceMapParserClient::new(&bytes)?.with_lookup_options(options);
+    if let Some(p) = &provider {
+      client = client.with_source_provider(p.as_ref());
+    }
+    // wasm 帧的编译后产物是二进制模块, 不附加
+    if !is_wasm {
+      let fallback = context.is_some();
+      client = attach_generated(client, key, a.generated.as_deref(), fallback, a.no_fetch)?;
+    }
+    clients.insert(key.clone(), client);
+  }
+  let mapped = MappedErrorStack::map_parsed(&es, engine, context, &|f| {
+    let key = keys.get(&(f.source_file, f.wasm_function.is_some()))?;
+    clients.get(key)
+  });
   let mut out = MapOutput {
+    engine,
     success: vec![],
     fail: vec![],
   };
-  let mut cache: HashMap<String, SourceMapParserClient> = HashMap::new();
-  for f in frames {
-    let key = map_key(a, f.source_file)?;
-    let client = match cache.get(&key) {
-      Some(c) => c,
-      None => {
-        let bytes = load_map(&key, a.no_fetch)?;
-        cache.insert(key.clone(), SourceMapParserClient::new(&bytes)?);
-        cache.get(&key).unwrap()
-      }
-    };
-    if a.context > 0 {
-      match client.lookup_token_with_context(f.line, f.column, a.context) {
-        Some(t) => out.success.push(MappedFrame {
-          raw: f.original_raw.to_string(),
-          source: t.src,
-          line: t.line,
-          column: t.column,
-          name: f.name.to_string(),
-          context: Some(serde_json::to_value(t.source_code)?),
-        }),
-        None => out.fail.push(FailedFrame {
-          raw: f.original_raw.to_string(),
-          reason: "no matching source token".into(),
-        }),
-      }
-    } else {
-      match client.lookup_token(f.line, f.column) {
-        Some(t) => out.success.push(MappedFrame {
-          raw: f.original_raw.to_string(),
-          source: t.src.unwrap_or_default(),
-          line: t.line,
-          column: t.column,
-          name: f.name.to_string(),
-          context: None,
-        }),
-        None => out.fail.push(FailedFrame {
-          raw: f.original_raw.to_string(),
-          reason: "no matching source token".into(),
-        }),
-      }
+  for f in mapped.all_frames() {
+    match &f.result {
+      FrameResult::Mapped(loc) => out.success.push(MappedFrame {
+        raw: f.raw.clone(),
+        source: loc.src.clone().unwrap_or_default(),
+        line: loc.line,
+        column: loc.column,
+        name: loc
+          .function_name
+          .as_ref()
+          .map(|n| n.name.clone())
+          .or_else(|| loc.name.clone())
+          .unwrap_or_else(|| f.frame.name.clone()),
+        name_source: loc.function_name.as_ref().map(|n| n.source),
+        column_distance: loc.column_distance,
+        context: loc.context.clone(),
+        span: loc.span,
+        generated: loc.generated.clone(),
+        frame: f.frame.clone(),
+      }),
+      FrameResult::Failed { reason } => out.fail.push(FailedFrame {
+        raw: f.raw.clone(),
+        reason: reason.to_string(),
+        frame: f.frame.clone(),
+      }),
     }
   }
   if !cli.quiet {
     eprintln!("mapped: {}, failed: {}", out.success.len(), out.fail.len());
   }
   let code = if out.fail.is_empty() { 0 } else { 2 };
-  write_json(cli, &out)?;
+  if a.text {
+    let frame_options = CodeFrameOptions {
+      color: a.color.enabled(cli),
+    };
+    write_text(cli, &mapped.render_with_code_frames(frame_options))?;
+  } else {
+    write_json(cli, &out)?;
+  }
   Ok(code)
 }
+
 fn map_key(a: &MapArgs, url: &str) -> Result<String, CliError> {
   if let Some(p) = &a.map {
     Ok(p.display().to_string())
   } else if let Some(d) = &a.map_dir {
-    let base = Path::new(url)
+    // Metro 开发服务器 URL 带查询串, 如 index.bundle?platform=ios
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let base = Path::new(path)
       .file_name()
       .and_then(|s| s.to_str())
-      .unwrap_or(url);
+      .unwrap_or(path);
     let name = if base.ends_with(".map") {
       base.to_string()
     } else {
@@ -308,6 +780,26 @@ fn map_key(a: &MapArgs, url: &str) -> Result<String, CliError> {
     Err(CliError::Msg("one sourcemap source is required".into()))
   }
 }
+/// wasm 帧专用的 map: --wasm-map 优先, 其次取 --wasm 模块 sourceMappingURL 段
+fn wasm_map_key(a: &MapArgs) -> Result<Option<String>, CliError> {
+  if let Some(p) = &a.wasm_map {
+    return Ok(Some(p.display().to_string()));
+  }
+  let Some(module) = &a.wasm else {
+    return Ok(None);
+  };
+  let url = source_map_parser::wasm::source_mapping_url(&fs::read(module)?).ok_or_else(|| {
+    CliError::Msg(format!(
+      "{} has no sourceMappingURL section",
+      module.display()
+    ))
+  })?;
+  if url.contains("://") {
+    return Ok(Some(url));
+  }
+  let dir = module.parent().unwrap_or(Path::new(""));
+  Ok(Some(dir.join(url).display().to_string()))
+}
 fn find_map(dir: &Path, name: &str) -> Option<PathBuf> {
   for e in fs::read_dir(dir).ok()? {
     let p = e.ok()?.path();
@@ -321,6 +813,48 @@ fn find_map(dir: &Path, name: &str) -> Option<PathBuf> {
   }
   None
 }
+/// 按 --generated 为 client 附加编译后代码: 本地文件, 或 auto 时取 map 的 `file` 字段相对 map 位置解析
+/// (map 为 URL 时同样按 URL 拉取); auto 下 map 无 `file` 字段或文件读取失败时仅提示并跳过
+///
+/// `fallback` 为要求上下文: 未指定 --generated 而 map 缺少 sourcesContent 时静默按 auto 查找, 作为回退上下文
+fn attach_generated(
+  client: SourceMapParserClient,
+  map_key: &str,
+  generated: Option<&str>,
+  fallback: bool,
+  no_fetch: bool,
+) -> Result<SourceMapParserClient, CliError> {
+  let (generated, quiet) = match generated {
+    Some(g) => (g, false),
+    None if fallback && client.missing_sources_content() => ("auto", true),
+    None => return Ok(client),
+  };
+  let auto = generated == "auto";
+  let key = match generated {
+    "auto" => match client.generated_file() {
+      Some(file) if file.contains("://") || Path::new(file).is_absolute() => file.to_string(),
+      Some(file) => match map_key.rfind('/') {
+        Some(i) => format!("{}{file}", &map_key[..=i]),
+        None => file.to_string(),
+      },
+      None => return Ok(client),
+    },
+    path => path.to_string(),
+  };
+  let bytes = match load_map(&key, no_fetch) {
+    Ok(bytes) => bytes,
+    Err(e) if auto => {
+      if !quiet {
+        eprintln!("generated file {key} skipped: {e}");
+      }
+      return Ok(client);
+    }
+    Err(e) => return Err(e),
+  };
+  let source = String::from_utf8(bytes)
+    .map_err(|_| CliError::Msg(format!("generated file {key} is not valid UTF-8")))?;
+  Ok(client.with_generated_source(source))
+}
 fn load_map(key: &str, no_fetch: bool) -> Result<Vec<u8>, CliError> {
   if key.starts_with("http://") || key.starts_with("https://") {
     if no_fetch {
@@ -338,33 +872,55 @@ fn load_map(key: &str, no_fetch: bool) -> Result<Vec<u8>, CliError> {
   }
 }
 fn run_lookup(cli: &Cli, a: &LookupArgs) -> Result<i32, CliError> {
-  let c = client_from_path(&a.map)?;
-  let out = if let Some(ctx) = a.context {
-    let t = c
-      .lookup_token_with_context(a.line, a.column, ctx)
-      .ok_or_else(|| CliError::Msg("no matching source token".into()))?;
+  let mut c = client_from_path(&a.map)?.with_lookup_options(a.lookup.options(ColumnBase::Zero));
+  let context = a.context_args.options(a.context);
+  if context.is_some() {
+    if let Some(p) = a.source_args.provider()? {
+      c = c.with_source_provider(p.as_ref());
+    }
+  }
+  let map_key = a.map.to_string_lossy();
+  let c = attach_generated(c, &map_key, a.generated.as_deref(), context.is_some(), true)?;
+  let generated = c.lookup_generated_excerpt(a.line, a.column, context.unwrap_or_default());
+  let out = if let Some(ctx) = context {
+    let t = c.try_lookup_token_with_context(a.line, a.column, ctx)?;
     LookupOutput {
       source: t.src,
       line: t.line,
       column: t.column,
-      name: None,
-      context: Some(serde_json::to_value(t.source_code)?),
+      name: t.name,
+      column_distance: t.column_distance,
+      context: (!t.source_code.is_empty())
+        .then(|| serde_json::to_value(&t.source_code))
+        .transpose()?,
+      span: t.span,
+      generated,
     }
   } else {
-    let t = c
-      .lookup_token(a.line, a.column)
-      .ok_or_else(|| CliError::Msg("no matching source token".into()))?;
+    let t = c.try_lookup_token(a.line, a.column)?;
     LookupOutput {
       source: t.src.unwrap_or_default(),
       line: t.line,
       column: t.column,
-      name: None,
+      name: t.name,
+      column_distance: t.column_distance,
       context: None,
+      span: None,
+      generated,
     }
   };
   write_json(cli, &out)?;
   Ok(0)
 }
+fn run_reverse(cli: &Cli, a: &ReverseArgs) -> Result<i32, CliError> {
+  let c = client_from_path(&a.map)?;
+  let positions = c.lookup_generated_positions(&a.source, a.line, a.column);
+  if positions.is_empty() {
+    return Err(CliError::Msg("no matching generated position".into()));
+  }
+  write_json(cli, &positions)?;
+  Ok(0)
+}
 fn run_unpack(cli: &Cli, a: &UnpackArgs) -> Result<i32, CliError> {
   let c = client_from_path(&a.map)?;
   let sources = c.unpack_all_sources();
@@ -389,3 +945,11 @@ fn run_unpack(cli: &Cli, a: &UnpackArgs) -> Result<i32, CliError> {
   write_json(cli, &sources)?;
   Ok(0)
 }
+fn run_compose(cli: &Cli, a: &ComposeArgs) -> Result<i32, CliError> {
+  let contents = a.maps.iter().map(fs::read).collect::<Result<Vec<_>, _>>()?;
+  let slices: Vec<&[u8]> = contents.iter().map(|c| c.as_slice()).collect();
+  let c = SourceMapParserClient::new_composed(&slices)?;
+  let composed: serde_json::Value = serde_json::from_slice(&c.to_sourcemap_json()?)?;
+  write_json(cli, &composed)?;
+  Ok(0)
+}
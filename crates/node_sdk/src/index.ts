@@ -8,19 +8,35 @@ import type {
   Token,
   GenerateResult,
   WasmContextSnippet,
+  GeneratedPosition,
   MappedErrorStack,
+  LookupFailure,
+  ContextOptions,
 } from './types';
 
 export type {
   StackFrame,
+  EvalOrigin,
+  FrameInfo,
   SourceMapToken,
+  OriginalFunctionName,
   TokenSourceCodeLine,
   Token,
   GenerateFailStack,
   GenerateResult,
   WasmContextFrameLine,
   WasmContextSnippet,
+  ContextOptions,
+  HighlightSpan,
+  GeneratedExcerpt,
+  GeneratedLine,
+  GeneratedPosition,
   MappedErrorStack,
+  MappedStackFrame,
+  MappedLocation,
+  FrameFailure,
+  LookupError,
+  LookupFailure,
 } from './types';
 
 // 低层 wasm 模块的函数签名（返回 JSON 字符串）
@@ -29,27 +45,80 @@ type LowLevelModule = {
     stack_raw: string,
     formatter: ((path: string) => string) | null,
     resolver: ((path: string) => string | null | undefined) | null,
-    on_error: ((stack_line_raw: string, error_message: string) => void) | null
+    on_error: ((stack_line_raw: string, error_message: string) => void) | null,
+    legacy_line_numbering?: boolean | null
   ) => string;
   generate_token_by_single_stack: (
     line: number,
     column: number,
     source_map_content: string,
-    context_offset: number | null
+    context_offset: number | null,
+    legacy_line_numbering?: boolean | null
   ) => string; // JSON of Token | null
   lookup_token: (sm: string, line: number, column: number) => string; // JSON of SourceMapToken | null
+  try_lookup_token: (sm: string, line: number, column: number) => string; // JSON of SourceMapToken | LookupFailure
   lookup_token_with_context: (
     sm: string,
     line: number,
     column: number,
-    context_lines: number
+    context_lines: number,
+    context_after?: number | null,
+    max_line_width?: number | null,
+    generated_source?: string | null
   ) => string; // JSON of Token | null
+  try_lookup_token_with_context: (
+    sm: string,
+    line: number,
+    column: number,
+    context_lines: number,
+    context_after?: number | null,
+    max_line_width?: number | null,
+    generated_source?: string | null
+  ) => string; // JSON of Token | LookupFailure
   lookup_context: (
     sm: string,
     line: number,
     column: number,
-    context_lines: number
+    context_lines: number,
+    context_after?: number | null,
+    max_line_width?: number | null,
+    generated_source?: string | null
   ) => string; // JSON of WasmContextSnippet | null
+  try_lookup_context: (
+    sm: string,
+    line: number,
+    column: number,
+    context_lines: number,
+    context_after?: number | null,
+    max_line_width?: number | null,
+    generated_source?: string | null
+  ) => string; // JSON of WasmContextSnippet | LookupFailure
+  lookup_code_frame: (
+    sm: string,
+    line: number,
+    column: number,
+    context_lines: number,
+    color: boolean | null,
+    context_after?: number | null,
+    max_line_width?: number | null,
+    generated_source?: string | null
+  ) => string; // JSON of string | null
+  try_lookup_code_frame: (
+    sm: string,
+    line: number,
+    column: number,
+    context_lines: number,
+    color: boolean | null,
+    context_after?: number | null,
+    max_line_width?: number | null,
+    generated_source?: string | null
+  ) => string; // JSON of string | LookupFailure
+  lookup_generated_positions: (
+    sm: string,
+    source: string,
+    line: number,
+    column: number | null
+  ) => string; // JSON of GeneratedPosition[]
   map_stack_line: (sm: string, stack_line: string) => string; // JSON of SourceMapToken | null
   map_stack_line_with_context: (
     sm: string,
@@ -60,7 +129,10 @@ type LowLevelModule = {
   map_error_stack: (
     sm: string,
     error_stack_raw: string,
-    context_lines: number | null
+    context_lines: number | null,
+    context_after?: number | null,
+    max_line_width?: number | null,
+    generated_source?: string | null
   ) => string; // JSON of MappedErrorStack
 };
 
@@ -103,6 +175,14 @@ function getWasmOrThrow(): LowLevelModule {
   return wasm;
 }
 
+/**
+ * number | ContextOptions -> wasm 层的 (context_lines, context_after, max_line_width)。
+ */
+function contextArgs(context: number | ContextOptions): [number, number | null, number | null] {
+  if (typeof context === 'number') return [context, null, null];
+  const before = context.before ?? 0;
+  return [before, context.after ?? before, context.max_line_width ?? null];
+}
 
 // ============ 与 d.ts 对齐的高层封装（返回值均已 JSON.parse） ============
 /**
@@ -111,6 +191,7 @@ function getWasmOrThrow(): LowLevelModule {
  * @param formatter 可选，对每个堆栈中的 source_file 进行重写（如增加 .map 后缀或路径映射）
  * @param resolver 可选，输入 (source_file_path) -> sourcemap 内容字符串；不提供时此帧将记为失败
  * @param on_error 可选，失败回调 (stack_line_raw, error_message)
+ * @param legacy_line_numbering 可选，为 true 时 token.source_code 的行号为 0-based（旧版输出），默认 1-based
  * @returns 解析后成功/失败列表与解析出的堆栈帧
  *
  * @example
@@ -140,13 +221,15 @@ export function generate_token_by_stack_raw(
   stack_raw: string,
   formatter?: (filePath: string) => string,
   resolver?: (filePath: string) => string | undefined,
-  on_error?: (rawLine: string, message: string) => void
+  on_error?: (rawLine: string, message: string) => void,
+  legacy_line_numbering?: boolean
 ): GenerateResult {
   const raw = getWasmOrThrow().generate_token_by_stack_raw(
     stack_raw,
     formatter ?? null,
     resolver ?? null,
-    on_error ?? null
+    on_error ?? null,
+    legacy_line_numbering ?? null
   );
   return JSON.parse(raw);
 }
@@ -157,6 +240,7 @@ export function generate_token_by_stack_raw(
  * @param column 编译后列号
  * @param source_map_content Source Map 原始内容（字符串）
  * @param context_offset 可选，上下文扩展的行数（向前/向后）
+ * @param legacy_line_numbering 可选，为 true 时 source_code 的行号为 0-based（旧版输出），默认 1-based
  * @returns 含上下文的 Token 或 null（无法定位时）
  *
  * @example
@@ -173,13 +257,15 @@ export function generate_token_by_single_stack(
   line: number,
   column: number,
   source_map_content: string,
-  context_offset?: number
+  context_offset?: number,
+  legacy_line_numbering?: boolean
 ): Token | null {
   const raw = getWasmOrThrow().generate_token_by_single_stack(
     line,
     column,
     source_map_content,
-    context_offset ?? null
+    context_offset ?? null,
+    legacy_line_numbering ?? null
   );
   return JSON.parse(raw);
 }
@@ -189,7 +275,7 @@ export function generate_token_by_single_stack(
  * @param source_map_content Source Map 原始内容（字符串）
  * @param line 1-based 编译后行号
  * @param column 编译后列号
- * @returns SourceMapToken 或 null
+ * @returns SourceMapToken 或 null（无法定位时；需要失败原因时用 try_lookup_token）
  *
  * @example
  * ```ts
@@ -210,13 +296,35 @@ export function lookup_token(
   return JSON.parse(raw);
 }
 
+/**
+ * 同 lookup_token，无法定位时返回 LookupFailure（`error` 为描述，`kind` 为具体原因）而非 null。
+ *
+ * @example
+ * ```ts
+ * import { init, try_lookup_token } from 'source_map_parser_node';
+ * await init();
+ *
+ * const res = try_lookup_token('{...map...}', 1, 200);
+ * if ('kind' in res) console.warn(res.kind, res.error);
+ * ```
+ */
+export function try_lookup_token(
+  source_map_content: string,
+  line: number,
+  column: number
+): SourceMapToken | LookupFailure {
+  const raw = getWasmOrThrow().try_lookup_token(source_map_content, line, column);
+  return JSON.parse(raw);
+}
+
 /**
  * 定位单点并携带上下文源码。
  * @param source_map_content Source Map 原始内容（字符串）
  * @param line 1-based 编译后行号
  * @param column 编译后列号
- * @param context_lines 上下文扩展的行数
- * @returns Token 或 null
+ * @param context_lines 上下文扩展的行数，或分别指定前后行数与最大行宽的 ContextOptions
+ * @param generated_source 可选，编译后 JS 代码；缺少 sourcesContent 时以其摘录回退为 token.generated
+ * @returns Token；无法定位，或缺少 sourcesContent 且未传入编译后代码时返回 null
  *
  * @example
  * ```ts
@@ -231,13 +339,35 @@ export function lookup_token_with_context(
   source_map_content: string,
   line: number,
   column: number,
-  context_lines: number
+  context_lines: number | ContextOptions,
+  generated_source?: string
 ): Token | null {
   const raw = getWasmOrThrow().lookup_token_with_context(
     source_map_content,
     line,
     column,
-    context_lines
+    ...contextArgs(context_lines),
+    generated_source ?? null
+  );
+  return JSON.parse(raw);
+}
+
+/**
+ * 同 lookup_token_with_context，失败时返回 LookupFailure 而非 null。
+ */
+export function try_lookup_token_with_context(
+  source_map_content: string,
+  line: number,
+  column: number,
+  context_lines: number | ContextOptions,
+  generated_source?: string
+): Token | LookupFailure {
+  const raw = getWasmOrThrow().try_lookup_token_with_context(
+    source_map_content,
+    line,
+    column,
+    ...contextArgs(context_lines),
+    generated_source ?? null
   );
   return JSON.parse(raw);
 }
@@ -247,8 +377,9 @@ export function lookup_token_with_context(
  * @param source_map_content Source Map 原始内容（字符串）
  * @param line 1-based 编译后行号
  * @param column 编译后列号
- * @param context_lines 上下文扩展的行数
- * @returns WasmContextSnippet 或 null
+ * @param context_lines 上下文扩展的行数，或 ContextOptions
+ * @param generated_source 可选，编译后 JS 代码；缺少 sourcesContent 时以其摘录回退为 snippet.generated
+ * @returns WasmContextSnippet；无法定位，或缺少 sourcesContent 且未传入编译后代码时返回 null
  *
  * @example
  * ```ts
@@ -263,13 +394,133 @@ export function lookup_context(
   source_map_content: string,
   line: number,
   column: number,
-  context_lines: number
+  context_lines: number | ContextOptions,
+  generated_source?: string
 ): WasmContextSnippet | null {
   const raw = getWasmOrThrow().lookup_context(
     source_map_content,
     line,
     column,
-    context_lines
+    ...contextArgs(context_lines),
+    generated_source ?? null
+  );
+  return JSON.parse(raw);
+}
+
+/**
+ * 同 lookup_context，失败时返回 LookupFailure 而非 null。
+ */
+export function try_lookup_context(
+  source_map_content: string,
+  line: number,
+  column: number,
+  context_lines: number | ContextOptions,
+  generated_source?: string
+): WasmContextSnippet | LookupFailure {
+  const raw = getWasmOrThrow().try_lookup_context(
+    source_map_content,
+    line,
+    column,
+    ...contextArgs(context_lines),
+    generated_source ?? null
+  );
+  return JSON.parse(raw);
+}
+
+/**
+ * 定位单点并将上下文渲染为代码帧文本（行号栏 + `>` 标记目标行 + `^` 标出映射表达式）。
+ * @param source_map_content Source Map 原始内容（字符串）
+ * @param line 1-based 编译后行号
+ * @param column 编译后列号
+ * @param context_lines 上下文扩展的行数，或 ContextOptions
+ * @param color 可选，是否输出 ANSI 颜色，默认 false
+ * @param generated_source 可选，编译后 JS 代码；缺少 sourcesContent 时渲染其摘录
+ * @returns 代码帧文本；无法定位，或缺少 sourcesContent 且未传入编译后代码时返回 null
+ *
+ * @example
+ * ```ts
+ * import { init, lookup_code_frame } from 'source_map_parser_node';
+ * await init();
+ *
+ * const frame = lookup_code_frame('{...map...}', 1, 120, 2, process.stdout.isTTY);
+ * if (frame !== null) console.log(frame);
+ * ```
+ */
+export function lookup_code_frame(
+  source_map_content: string,
+  line: number,
+  column: number,
+  context_lines: number | ContextOptions,
+  color?: boolean,
+  generated_source?: string
+): string | null {
+  const [before, after, max_line_width] = contextArgs(context_lines);
+  const raw = getWasmOrThrow().lookup_code_frame(
+    source_map_content,
+    line,
+    column,
+    before,
+    color ?? null,
+    after,
+    max_line_width,
+    generated_source ?? null
+  );
+  return JSON.parse(raw);
+}
+
+/**
+ * 同 lookup_code_frame，失败时返回 LookupFailure 而非 null。
+ */
+export function try_lookup_code_frame(
+  source_map_content: string,
+  line: number,
+  column: number,
+  context_lines: number | ContextOptions,
+  color?: boolean,
+  generated_source?: string
+): string | LookupFailure {
+  const [before, after, max_line_width] = contextArgs(context_lines);
+  const raw = getWasmOrThrow().try_lookup_code_frame(
+    source_map_content,
+    line,
+    column,
+    before,
+    color ?? null,
+    after,
+    max_line_width,
+    generated_source ?? null
+  );
+  return JSON.parse(raw);
+}
+
+/**
+ * 反查：原始源码路径 + 行/列 -> 所有对应的编译后位置。
+ * @param source_map_content Source Map 原始内容（字符串）
+ * @param source 原始源码路径，可只给路径后缀（如 `src/a.ts` 匹配 `webpack:///./src/a.ts`）
+ * @param line 1-based 原始行号
+ * @param column 可选，原始列号；该列无映射时取同行之后最近的列，不传则返回整行
+ * @returns GeneratedPosition 列表（按编译后位置排序，无匹配时为空数组）
+ *
+ * @example
+ * ```ts
+ * import { init, lookup_generated_positions } from 'source_map_parser_node';
+ * await init();
+ *
+ * const positions = lookup_generated_positions('{...map...}', 'src/App.tsx', 42, 8);
+ * console.log(positions.map((p) => `${p.line}:${p.column}`));
+ * ```
+ */
+export function lookup_generated_positions(
+  source_map_content: string,
+  source: string,
+  line: number,
+  column?: number
+): GeneratedPosition[] {
+  const raw = getWasmOrThrow().lookup_generated_positions(
+    source_map_content,
+    source,
+    line,
+    column ?? null
   );
   return JSON.parse(raw);
 }
@@ -352,11 +603,13 @@ export function map_stack_trace(
 
 /**
  * 将完整错误堆栈（包含首行错误消息）映射。
- * - 若 context_lines 未提供，则返回 frames（不含上下文）。
- * - 若提供 context_lines，则返回 frames_with_context（含上下文）。
+ * - frames 与输入帧一一对应：成功时 status 为 mapped，失败时 status 为 failed 并带 reason。
+ * - 若提供 context_lines，映射成功的帧附带 context（上下文源码行）。
  * @param source_map_content Source Map 原始内容（字符串）
  * @param error_stack_raw 完整错误堆栈文本（首行为错误消息，其后为堆栈帧）
- * @param context_lines 可选，上下文扩展行数
+ * @param context_lines 可选，上下文扩展行数，或 ContextOptions
+ * @param generated_source 可选，编译后 JS 代码；映射成功的帧附带 generated 编译后代码摘录，
+ *   缺少 sourcesContent 时以其作为回退上下文
  *
  * @example
  * ```ts
@@ -366,18 +619,21 @@ export function map_stack_trace(
  * const errorStack = `Error: boom\n` +
  *   `    at fn (https://example.com/app.min.js:1:10)`;
  * const mapped = map_error_stack('{...map...}', errorStack, 2);
- * console.log(mapped.frames_with_context?.[0]?.original?.line);
+ * const first = mapped.frames[0];
+ * if (first?.status === 'mapped') console.log(first.src, first.line);
  * ```
  */
 export function map_error_stack(
   source_map_content: string,
   error_stack_raw: string,
-  context_lines?: number
+  context_lines?: number | ContextOptions,
+  generated_source?: string
 ): MappedErrorStack {
   const raw = getWasmOrThrow().map_error_stack(
     source_map_content,
     error_stack_raw,
-    context_lines ?? null
+    ...(context_lines === undefined ? ([null, null, null] as const) : contextArgs(context_lines)),
+    generated_source ?? null
   );
   return JSON.parse(raw);
 }
@@ -390,6 +646,7 @@ export function map_error_stack(
  * @param options.resolveSourceMap (path) => sourcemap 内容字符串
  * @param options.formatter 可选，格式化/重写 source_file 路径
  * @param options.onError 可选，记录失败信息
+ * @param options.legacyLineNumbering 可选，为 true 时 source_code 的行号为 0-based（旧版输出）
  *
  * @example
  * ```ts
@@ -411,13 +668,15 @@ export function mapErrorStackWithResolver(options: {
   resolveSourceMap: (filePath: string) => string | undefined;
   formatter?: (filePath: string) => string; // Rust 端会 unwrap 该返回值为字符串
   onError?: (rawLine: string, message: string) => void;
+  legacyLineNumbering?: boolean;
 }): GenerateResult {
-  const { errorStack, resolveSourceMap, formatter, onError } = options;
+  const { errorStack, resolveSourceMap, formatter, onError, legacyLineNumbering } = options;
   const raw = getWasmOrThrow().generate_token_by_stack_raw(
     errorStack,
     formatter ?? null,
     (p: string) => resolveSourceMap(p) ?? null,
-    onError ?? null
+    onError ?? null,
+    legacyLineNumbering ?? null
   );
   return JSON.parse(raw);
 }
@@ -430,6 +689,12 @@ export default {
   lookup_token,
   lookup_token_with_context,
   lookup_context,
+  lookup_code_frame,
+  try_lookup_token,
+  try_lookup_token_with_context,
+  try_lookup_context,
+  try_lookup_code_frame,
+  lookup_generated_positions,
   map_stack_line,
   map_stack_line_with_context,
   map_stack_trace,
@@ -1,8 +1,12 @@
 use serde::Serialize;
-use serde_json;
+use source_map_parser::{
+  code_frame::CodeFrameOptions,
+  context_window::{ContextOptions, HighlightSpan, LineNumbering},
+  generated_code::GeneratedExcerpt,
+  position::ColumnBase,
+  stack_transform, token_generator, LookupError, SourceMapParserClient,
+};
 use wasm_bindgen::prelude::*;
-use js_sys;
-use source_map_parser::{stack_transform, token_generator, SourceMapParserClient}; // for Function type
 
 #[derive(Clone, Debug, Serialize)]
 struct GenerateFailStack {
@@ -28,18 +32,36 @@ struct GenerateResult<'a> {
 /// resolver: (可选) 回调，输入 (source_file_path:String) -> sourcemap 内容字符串；
 ///           若未提供 resolver，将跳过该帧并记录失败。
 /// on_error: (可选) 失败回调 (stack_line_raw, error_message)
+/// legacy_line_numbering: (可选) 为 true 时 source_code 的行号为 0-based (旧版输出)
 #[wasm_bindgen]
 pub fn generate_token_by_stack_raw(
   stack_raw: String,
   formatter: Option<js_sys::Function>,
   resolver: Option<js_sys::Function>,
   on_error: Option<js_sys::Function>,
+  legacy_line_numbering: Option<bool>,
 ) -> JsValue {
   let error_stack = stack_transform::ErrorStack::from_raw(&stack_raw);
-  let mut token_generator = token_generator::GenerateToken::new();
+  let mut token_generator = token_generator::GenerateToken::new()
+    .with_line_numbering(line_numbering(legacy_line_numbering));
   let mut fail_stacks: Vec<GenerateFailStack> = Vec::new();
 
-  for stack in &error_stack.stacks {
+  for stack in error_stack.all_stacks() {
+    if let Some(reason) = stack.unmappable_reason() {
+      let reason = reason.to_string();
+      fail_stacks.push(GenerateFailStack {
+        original_raw: stack.original_raw.to_string(),
+        error_message: reason.clone(),
+      });
+      if let Some(on_error) = on_error.as_ref() {
+        let _ = on_error.call2(
+          &JsValue::null(),
+          &JsValue::from_str(stack.original_raw),
+          &JsValue::from_str(&reason),
+        );
+      }
+      continue;
+    }
     let mut source_file_path = stack.source_file.to_string();
 
     if let Some(format_fn) = formatter.as_ref() {
@@ -56,7 +78,8 @@ pub fn generate_token_by_stack_raw(
             token_generator.add_task(token_generator::GenerateTask {
               source_map_content: content,
               line: stack.line,
-              column: stack.column,
+              // GenerateTask 的列为 0-based, 浏览器堆栈列号为 1-based
+              column: stack.zero_based_column(ColumnBase::One),
               source_line_offset: Some(5),
             });
           } else {
@@ -68,7 +91,7 @@ pub fn generate_token_by_stack_raw(
             if let Some(on_error) = on_error.as_ref() {
               let _ = on_error.call2(
                 &JsValue::null(),
-                &JsValue::from_str(&stack.original_raw),
+                &JsValue::from_str(stack.original_raw),
                 &JsValue::from_str(&msg),
               );
             }
@@ -83,7 +106,7 @@ pub fn generate_token_by_stack_raw(
           if let Some(on_error) = on_error.as_ref() {
             let _ = on_error.call2(
               &JsValue::null(),
-              &JsValue::from_str(&stack.original_raw),
+              &JsValue::from_str(stack.original_raw),
               &JsValue::from_str(&err_str),
             );
           }
@@ -99,7 +122,7 @@ pub fn generate_token_by_stack_raw(
       if let Some(on_error) = on_error.as_ref() {
         let _ = on_error.call2(
           &JsValue::null(),
-          &JsValue::from_str(&stack.original_raw),
+          &JsValue::from_str(stack.original_raw),
           &JsValue::from_str(&msg),
         );
       }
@@ -109,7 +132,7 @@ pub fn generate_token_by_stack_raw(
   token_generator.generate();
 
   let result = GenerateResult {
-    stacks: error_stack.stacks.clone(),
+    stacks: error_stack.all_stacks().into_iter().cloned().collect(),
     success: token_generator.get_tokens(),
     fail: fail_stacks,
   };
@@ -119,17 +142,33 @@ pub fn generate_token_by_stack_raw(
   JsValue::from_str(&json)
 }
 
+/// `legacy` 为 true 时使用旧版行号约定
+fn line_numbering(legacy: Option<bool>) -> LineNumbering {
+  if legacy.unwrap_or(false) {
+    LineNumbering::Legacy
+  } else {
+    LineNumbering::OneBased
+  }
+}
+
 #[wasm_bindgen]
 pub fn generate_token_by_single_stack(
   line: u32,
   column: u32,
   source_map_content: String,
   context_offset: Option<u32>,
+  legacy_line_numbering: Option<bool>,
 ) -> JsValue {
-  let result: Option<token_generator::Token> =
-    token_generator::get_stack_source(&source_map_content, line, column, context_offset);
+  let result: Option<token_generator::Token> = token_generator::get_stack_source_with_numbering(
+    &source_map_content,
+    line,
+    column,
+    context_offset,
+    line_numbering(legacy_line_numbering),
+  );
 
-  let json = serde_json::to_string(&result).unwrap_or_else(|_| "{\"error\":\"serialization failed\"}".to_string());
+  let json = serde_json::to_string(&result)
+    .unwrap_or_else(|_| "{\"error\":\"serialization failed\"}".to_string());
 
   JsValue::from_str(&json)
 }
@@ -141,6 +180,8 @@ struct WasmContextFrameLine {
   line: u32,
   is_target: bool,
   code: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  column_offset: Option<u32>,
 }
 
 #[derive(Serialize)]
@@ -148,17 +189,78 @@ struct WasmContextSnippet {
   src: String,
   line: u32,
   column: u32,
+  name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  column_distance: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  span: Option<HighlightSpan>,
   context: Vec<WasmContextFrameLine>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  generated: Option<GeneratedExcerpt>,
+}
+
+/// 查询失败时返回的错误对象: `{ "error": 描述, "kind": 原因, ...原因相关字段 }`
+#[derive(Serialize)]
+struct LookupFailure<'a> {
+  error: String,
+  #[serde(flatten)]
+  detail: &'a LookupError,
+}
+
+/// `context_lines` 为目标行之前的行数, `context_after` 缺省时与之相同
+fn context_options(
+  context_lines: u32,
+  context_after: Option<u32>,
+  max_line_width: Option<u32>,
+) -> ContextOptions {
+  ContextOptions {
+    before: context_lines,
+    after: context_after.unwrap_or(context_lines),
+    max_line_width,
+  }
+}
+
+/// 附加编译后代码: 缺少 sourcesContent 时上下文以其摘录回退, `map_error_stack` 的帧附带编译后代码摘录
+fn with_generated(
+  client: SourceMapParserClient,
+  generated_source: Option<String>,
+) -> SourceMapParserClient {
+  match generated_source {
+    Some(source) => client.with_generated_source(source),
+    None => client,
+  }
+}
+
+/// `typed` 为 false 时失败返回 `null` (`lookup_*`), 为 true 时返回 [`LookupFailure`] (`try_lookup_*`)
+fn lookup_json<T: Serialize>(result: Result<T, LookupError>, typed: bool) -> JsValue {
+  let json = match result {
+    Ok(v) => serde_json::to_string(&v),
+    Err(e) if typed => serde_json::to_string(&LookupFailure {
+      error: e.to_string(),
+      detail: &e,
+    }),
+    Err(_) => Ok("null".to_string()),
+  };
+  JsValue::from_str(&json.unwrap())
 }
 
 #[wasm_bindgen]
 pub fn lookup_token(source_map_content: &str, line: u32, column: u32) -> JsValue {
+  token_lookup(source_map_content, line, column, false)
+}
+
+/// 同 [`lookup_token`], 失败时返回 `{ error, kind, ... }` 而非 null
+#[wasm_bindgen]
+pub fn try_lookup_token(source_map_content: &str, line: u32, column: u32) -> JsValue {
+  token_lookup(source_map_content, line, column, true)
+}
+
+fn token_lookup(source_map_content: &str, line: u32, column: u32, typed: bool) -> JsValue {
   let client = match SourceMapParserClient::new(source_map_content.as_bytes()) {
     Ok(c) => c,
     Err(e) => return JsValue::from_str(&format!("{{\"error\":\"{}\"}}", e)),
   };
-  let tok = client.lookup_token(line, column);
-  JsValue::from_str(&serde_json::to_string(&tok).unwrap())
+  lookup_json(client.try_lookup_token(line, column), typed)
 }
 
 #[wasm_bindgen]
@@ -167,13 +269,58 @@ pub fn lookup_token_with_context(
   line: u32,
   column: u32,
   context_lines: u32,
+  context_after: Option<u32>,
+  max_line_width: Option<u32>,
+  generated_source: Option<String>,
+) -> JsValue {
+  token_with_context_lookup(
+    source_map_content,
+    line,
+    column,
+    context_options(context_lines, context_after, max_line_width),
+    generated_source,
+    false,
+  )
+}
+
+/// 同 [`lookup_token_with_context`], 失败时返回 `{ error, kind, ... }` 而非 null
+#[wasm_bindgen]
+pub fn try_lookup_token_with_context(
+  source_map_content: &str,
+  line: u32,
+  column: u32,
+  context_lines: u32,
+  context_after: Option<u32>,
+  max_line_width: Option<u32>,
+  generated_source: Option<String>,
+) -> JsValue {
+  token_with_context_lookup(
+    source_map_content,
+    line,
+    column,
+    context_options(context_lines, context_after, max_line_width),
+    generated_source,
+    true,
+  )
+}
+
+fn token_with_context_lookup(
+  source_map_content: &str,
+  line: u32,
+  column: u32,
+  context: ContextOptions,
+  generated_source: Option<String>,
+  typed: bool,
 ) -> JsValue {
   let client = match SourceMapParserClient::new(source_map_content.as_bytes()) {
     Ok(c) => c,
     Err(e) => return JsValue::from_str(&format!("{{\"error\":\"{}\"}}", e)),
   };
-  let tok = client.lookup_token_with_context(line, column, context_lines);
-  JsValue::from_str(&serde_json::to_string(&tok).unwrap())
+  let client = with_generated(client, generated_source);
+  lookup_json(
+    client.try_lookup_token_with_context(line, column, context),
+    typed,
+  )
 }
 
 #[wasm_bindgen]
@@ -182,17 +329,64 @@ pub fn lookup_context(
   line: u32,
   column: u32,
   context_lines: u32,
+  context_after: Option<u32>,
+  max_line_width: Option<u32>,
+  generated_source: Option<String>,
+) -> JsValue {
+  context_lookup(
+    source_map_content,
+    line,
+    column,
+    context_options(context_lines, context_after, max_line_width),
+    generated_source,
+    false,
+  )
+}
+
+/// 同 [`lookup_context`], 失败时返回 `{ error, kind, ... }` 而非 null
+#[wasm_bindgen]
+pub fn try_lookup_context(
+  source_map_content: &str,
+  line: u32,
+  column: u32,
+  context_lines: u32,
+  context_after: Option<u32>,
+  max_line_width: Option<u32>,
+  generated_source: Option<String>,
+) -> JsValue {
+  context_lookup(
+    source_map_content,
+    line,
+    column,
+    context_options(context_lines, context_after, max_line_width),
+    generated_source,
+    true,
+  )
+}
+
+fn context_lookup(
+  source_map_content: &str,
+  line: u32,
+  column: u32,
+  context: ContextOptions,
+  generated_source: Option<String>,
+  typed: bool,
 ) -> JsValue {
   let client = match SourceMapParserClient::new(source_map_content.as_bytes()) {
     Ok(c) => c,
     Err(e) => return JsValue::from_str(&format!("{{\"error\":\"{}\"}}", e)),
   };
+  let client = with_generated(client, generated_source);
   let snippet = client
-    .lookup_context(line, column, context_lines)
+    .try_lookup_context(line, column, context)
     .map(|s| WasmContextSnippet {
       src: s.src,
       line: s.line,
       column: s.column,
+      name: s.name,
+      column_distance: s.column_distance,
+      span: s.span,
+      generated: s.generated,
       context: s
         .context
         .into_iter()
@@ -200,10 +394,100 @@ pub fn lookup_context(
           line: l.line,
           is_target: l.is_target,
           code: l.code,
+          column_offset: l.column_offset,
         })
         .collect(),
     });
-  JsValue::from_str(&serde_json::to_string(&snippet).unwrap())
+  lookup_json(snippet, typed)
+}
+
+/// 定位单点并将上下文渲染为代码帧文本 (行号栏 + `^` 标出映射表达式), `color` 为 true 时带 ANSI 颜色
+#[wasm_bindgen]
+// wasm 导出按位置参数传递, 与 TS 封装层的可选参数一一对应
+#[allow(clippy::too_many_arguments)]
+pub fn lookup_code_frame(
+  source_map_content: &str,
+  line: u32,
+  column: u32,
+  context_lines: u32,
+  color: Option<bool>,
+  context_after: Option<u32>,
+  max_line_width: Option<u32>,
+  generated_source: Option<String>,
+) -> JsValue {
+  code_frame_lookup(
+    source_map_content,
+    line,
+    column,
+    context_options(context_lines, context_after, max_line_width),
+    color,
+    generated_source,
+    false,
+  )
+}
+
+/// 同 [`lookup_code_frame`], 失败时返回 `{ error, kind, ... }` 而非 null
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn try_lookup_code_frame(
+  source_map_content: &str,
+  line: u32,
+  column: u32,
+  context_lines: u32,
+  color: Option<bool>,
+  context_after: Option<u32>,
+  max_line_width: Option<u32>,
+  generated_source: Option<String>,
+) -> JsValue {
+  code_frame_lookup(
+    source_map_content,
+    line,
+    column,
+    context_options(context_lines, context_after, max_line_width),
+    color,
+    generated_source,
+    true,
+  )
+}
+
+fn code_frame_lookup(
+  source_map_content: &str,
+  line: u32,
+  column: u32,
+  context: ContextOptions,
+  color: Option<bool>,
+  generated_source: Option<String>,
+  typed: bool,
+) -> JsValue {
+  let client = match SourceMapParserClient::new(source_map_content.as_bytes()) {
+    Ok(c) => c,
+    Err(e) => return JsValue::from_str(&format!("{{\"error\":\"{}\"}}", e)),
+  };
+  let client = with_generated(client, generated_source);
+  let options = CodeFrameOptions {
+    color: color.unwrap_or(false),
+  };
+  lookup_json(
+    client
+      .try_lookup_context(line, column, context)
+      .map(|s| s.code_frame(options)),
+    typed,
+  )
+}
+
+#[wasm_bindgen]
+pub fn lookup_generated_positions(
+  source_map_content: &str,
+  source: &str,
+  line: u32,
+  column: Option<u32>,
+) -> JsValue {
+  let client = match SourceMapParserClient::new(source_map_content.as_bytes()) {
+    Ok(c) => c,
+    Err(e) => return JsValue::from_str(&format!("{{\"error\":\"{}\"}}", e)),
+  };
+  let positions = client.lookup_generated_positions(source, line, column);
+  JsValue::from_str(&serde_json::to_string(&positions).unwrap())
 }
 
 #[wasm_bindgen]
@@ -245,11 +529,21 @@ pub fn map_error_stack(
   source_map_content: &str,
   error_stack_raw: &str,
   context_lines: Option<u32>,
+  context_after: Option<u32>,
+  max_line_width: Option<u32>,
+  generated_source: Option<String>,
 ) -> JsValue {
   let client = match SourceMapParserClient::new(source_map_content.as_bytes()) {
     Ok(c) => c,
     Err(e) => return JsValue::from_str(&format!("{{\"error\":\"{}\"}}", e)),
   };
-  let mapped = client.map_error_stack(error_stack_raw, context_lines);
+  let client = with_generated(client, generated_source);
+  let mapped = match context_lines {
+    Some(n) => client.map_error_stack_with_context(
+      error_stack_raw,
+      context_options(n, context_after, max_line_width),
+    ),
+    None => client.map_error_stack(error_stack_raw, None),
+  };
   JsValue::from_str(&serde_json::to_string(&mapped).unwrap())
 }
@@ -10,7 +10,7 @@
  * 单条堆栈帧（由编译后堆栈解析得到）。
  */
 export interface StackFrame {
-  /** 函数/方法名，可能为空字符串 */
+  /** 函数/方法名，已去除 `async `/`new ` 前缀与 `[as alias]` 后缀，可能为空字符串 */
   name: string;
   /** 编译后（1-based）行号 */
   line: number;
@@ -20,6 +20,83 @@ export interface StackFrame {
   source_file: string;
   /** 原始堆栈行文本（未修改） */
   original_raw: string;
+  /** 引擎内置函数帧（如 Safari `[native code]` / V8 `(native)`） */
+  is_native: boolean;
+  /** 异步帧（V8 `at async foo` / Firefox `async*foo@`） */
+  is_async: boolean;
+  /** 构造调用帧（V8 `at new Foo`） */
+  is_constructor: boolean;
+  /** 方法调用的接收者类型，如 `Foo.bar` 中的 `Foo` */
+  type_name: string | null;
+  /** 方法名，如 `Foo.bar` 中的 `bar` */
+  method_name: string | null;
+  /** V8 `[as alias]` 中的调用别名 */
+  method_alias: string | null;
+  /** V8 `Promise.all (index N)` 中的 N */
+  promise_index: number | null;
+  /** WebAssembly 帧的函数下标 `wasm-function[N]`；此时 line 固定为 1，column 为模块内字节偏移 */
+  wasm_function: number | null;
+  /** eval 执行的代码帧（如 Safari `eval code` / V8 `eval at ...`） */
+  is_eval: boolean;
+  /** V8 eval 帧的来源链，仅 V8 eval 帧存在 */
+  eval_origin?: EvalOrigin;
+}
+
+/**
+ * V8 eval 帧的来源信息。
+ *
+ * 无 `//# sourceURL` 时，StackFrame 的 source_file/line/column 为打包产物中最外层 eval 调用位置；
+ * 有 sourceURL 时为 sourceURL 与 eval 代码内的位置。
+ */
+export interface EvalOrigin {
+  /** 打包产物中调用 eval 的函数名（嵌套时取最外层） */
+  function_name: string;
+  /** 最外层 eval 调用所在文件 */
+  source_file: string;
+  /** 最外层 eval 调用所在（1-based）行号 */
+  line: number;
+  /** 最外层 eval 调用所在列号 */
+  column: number;
+  /** eval 代码内的（1-based）行号 */
+  eval_line: number;
+  /** eval 代码内的列号 */
+  eval_column: number;
+  /** eval 代码 `//# sourceURL` 声明的名称 */
+  source_url: string | null;
+}
+
+/**
+ * 帧的结构化属性与解析出的编译后位置，随 map_error_stack 的映射结果返回。
+ */
+export interface FrameInfo {
+  /** 函数/方法名，可能为空字符串 */
+  name: string;
+  /** 编译后文件 */
+  source_file: string;
+  /** 编译后（1-based）行号，帧不带位置时为 0 */
+  line: number;
+  /** 编译后列号，按输入原样保留 */
+  column: number;
+  /** 异步帧 */
+  is_async: boolean;
+  /** 构造调用帧 */
+  is_constructor: boolean;
+  /** 引擎内置函数帧 */
+  is_native: boolean;
+  /** eval 执行的代码帧 */
+  is_eval: boolean;
+  /** 方法调用的接收者类型 */
+  type_name: string | null;
+  /** 方法名 */
+  method_name: string | null;
+  /** `[as alias]` 中的调用别名 */
+  method_alias: string | null;
+  /** `Promise.all (index N)` 中的 N */
+  promise_index: number | null;
+  /** WebAssembly 帧的函数下标 */
+  wasm_function: number | null;
+  /** V8 eval 帧的来源链，仅 V8 eval 帧存在 */
+  eval_origin?: EvalOrigin;
 }
 
 /**
@@ -28,7 +105,7 @@ export interface StackFrame {
  * 注意：source_code/src 可能为 null，当 sourcemap 中缺少源码内容时会为空。
  */
 export interface SourceMapToken {
-  /** 原始源码（0-based）行号 */
+  /** 原始源码（1-based）行号 */
   line: number;
   /** 原始源码列号 */
   column: number;
@@ -36,25 +113,41 @@ export interface SourceMapToken {
   source_code: string | null;
   /** 原始源码路径（sourcemap 中的 sources），可能为 null */
   src: string | null;
+  /** 原始标识符（sourcemap 中的 names），未记录时为 null */
+  name: string | null;
+  /** 命中 token 与查询列的列距，token 不在查询行时省略 */
+  column_distance?: number;
+}
+
+/**
+ * 推断出的原始函数名。
+ */
+export interface OriginalFunctionName {
+  /** 函数名 */
+  name: string;
+  /** 推断方式：function_map 取自 Hermes / Metro map 函数映射；source_scan 扫描 sourcesContent；call_site 取调用方调用点 token */
+  source: 'function_map' | 'source_scan' | 'call_site';
 }
 
 /**
  * 上下文中的一行源码。
  */
 export interface TokenSourceCodeLine {
-  /** 此行的 0-based 行号（相对原始源码） */
+  /** 此行的 1-based 行号（相对原始源码） */
   line: number;
   /** 是否为堆栈命中的目标行 */
   is_stack_line: boolean;
-  /** 源码文本 */
+  /** 源码文本（超过 max_line_width 时为截断后的文本） */
   raw: string;
+  /** 行被截断时保留部分在原行中的起始列（不含开头的 …） */
+  column_offset?: number;
 }
 
 /**
  * 含上下文的定位结果（包含多行源码）。
  */
 export interface Token {
-  /** 原始源码（0-based）行号 */
+  /** 原始源码（1-based）行号 */
   line: number;
   /** 原始源码列号 */
   column: number;
@@ -62,6 +155,14 @@ export interface Token {
   source_code: TokenSourceCodeLine[];
   /** 原始源码路径（sourcemap 中的 sources） */
   src: string;
+  /** 原始标识符（sourcemap 中的 names），未记录时为 null */
+  name: string | null;
+  /** 命中 token 与查询列的列距，token 不在查询行时省略 */
+  column_distance?: number;
+  /** 目标行上映射表达式的范围，缺少 sourcesContent 时省略 */
+  span?: HighlightSpan;
+  /** 缺少 sourcesContent 且传入编译后代码时回退的编译后代码摘录（此时 source_code 为空） */
+  generated?: GeneratedExcerpt;
 }
 
 /**
@@ -90,39 +191,172 @@ export interface GenerateResult {
  * 通用上下文片段中的一行（lookup_context 用）。
  */
 export interface WasmContextFrameLine {
-  /** 行号（0-based） */
+  /** 行号（1-based） */
   line: number;
   /** 是否为目标行 */
   is_target: boolean;
-  /** 源码文本 */
+  /** 源码文本（超过 max_line_width 时为截断后的文本） */
+  code: string;
+  /** 行被截断时保留部分在原行中的起始列（不含开头的 …） */
+  column_offset?: number;
+}
+
+/**
+ * 上下文截取参数；传数字时等价于 `{ before: n, after: n }`。
+ */
+export interface ContextOptions {
+  /** 目标行之前的行数，默认 0 */
+  before?: number;
+  /** 目标行之后的行数，默认与 before 相同 */
+  after?: number;
+  /** 单行最大宽度（UTF-16 码元），超出时围绕目标列截取，省略部分以 … 标记；0 表示不截断 */
+  max_line_width?: number;
+}
+
+/**
+ * 目标行上映射表达式的范围：0-based UTF-16 列，左闭右开，可直接用于 `String.prototype.slice`。
+ */
+export interface HighlightSpan {
+  start: number;
+  end: number;
+}
+
+/**
+ * 编译后代码摘录中的一行。
+ */
+export interface GeneratedLine {
+  /** 所在编译后行号（1-based）；美化拆出的各行来自同一编译后行 */
+  line: number;
+  is_target: boolean;
   code: string;
 }
 
+/**
+ * 编译后代码在查询位置附近的摘录；目标行过长（压缩代码）时按语句拆行缩进。
+ */
+export interface GeneratedExcerpt {
+  /** 编译后行号（1-based），同查询 */
+  line: number;
+  /** 编译后列号，同查询 */
+  column: number;
+  /** 是否经过美化 */
+  beautified: boolean;
+  lines: GeneratedLine[];
+  /** 查询列到同一编译后行下一个映射之间的代码，为目标摘录行内的列 */
+  span?: HighlightSpan;
+}
+
 /**
  * 通用上下文片段（不依赖错误堆栈，而是直接通过行列定位）。
  */
 export interface WasmContextSnippet {
   /** 原始源码路径 */
   src: string;
-  /** 目标行（0-based） */
+  /** 目标行（1-based） */
   line: number;
   /** 目标列 */
   column: number;
+  /** 原始标识符（sourcemap 中的 names），未记录时为 null */
+  name: string | null;
+  /** 命中 token 与查询列的列距，token 不在查询行时省略 */
+  column_distance?: number;
+  /** 目标行上映射表达式的范围 */
+  span?: HighlightSpan;
   /** 上下文行集合（包含目标行） */
   context: WasmContextFrameLine[];
+  /** 缺少 sourcesContent 且传入编译后代码时回退的编译后代码摘录（此时 context 为空） */
+  generated?: GeneratedExcerpt;
+}
+
+/**
+ * 反查得到的编译后位置（lookup_generated_positions 用）。
+ */
+export interface GeneratedPosition {
+  /** 编译后（1-based）行号 */
+  line: number;
+  /** 编译后列号 */
+  column: number;
 }
 
 /**
  * 错误堆栈映射的聚合结果。
  *
- * - 当未传 context_lines 时，frames 填充简单 token；
- * - 当传入 context_lines 时，frames_with_context 填充含上下文的 token。
+ * frames 与输入帧一一对应、顺序一致；传入 context_lines 时映射成功的帧附带 context。
  */
 export interface MappedErrorStack {
-  /** 错误首行消息（如 ReferenceError: ...） */
+  /** 第一个帧之前的错误消息（可能多行；Firefox / Safari 的 error.stack 不含消息时为空字符串） */
   error_message: string;
-  /** 简单映射的帧（不含上下文） */
-  frames: SourceMapToken[];
-  /** 带上下文的帧集合 */
-  frames_with_context: Token[];
+  /** 每个输入帧的映射结果 */
+  frames: MappedStackFrame[];
+  /** 输入堆栈识别出的引擎 */
+  engine?: 'v8' | 'firefox' | 'safari' | 'node' | 'hermes';
+  /** `[cause]:` / `Caused by:` 对应的下一级错误 */
+  cause?: MappedErrorStack;
+  /** AggregateError 的子错误 */
+  errors?: MappedErrorStack[];
 }
+
+/**
+ * 单点查询失败的具体原因（kind 区分）。
+ */
+export type LookupError =
+  | { kind: 'line_zero' }
+  // 仅在传入编译后代码 (generated_source) 时出现
+  | { kind: 'line_out_of_range'; line: number; line_count: number }
+  | { kind: 'no_matching_token'; line: number; column: number }
+  | { kind: 'invalid_source_index'; src_id: number }
+  | { kind: 'no_source' }
+  | { kind: 'missing_source_content'; src: string };
+
+/**
+ * try_lookup_token / try_lookup_token_with_context / try_lookup_context / try_lookup_code_frame 失败时返回的错误对象。
+ */
+export type LookupFailure = { error: string } & LookupError;
+
+/**
+ * 帧未能映射的原因（kind 区分）：帧本身不可查询或没有对应 source map 时只有 kind，
+ * source map 查询失败时为 LookupError。
+ */
+export type FrameFailure =
+  | { kind: 'native_frame' }
+  | { kind: 'eval_frame' }
+  | { kind: 'no_position' }
+  | { kind: 'no_source_map' }
+  | LookupError;
+
+/**
+ * 帧映射到的原始位置。
+ */
+export interface MappedLocation {
+  /** 原始源码路径，可能为 null */
+  src: string | null;
+  /** 原始源码（1-based）行号 */
+  line: number;
+  /** 原始源码列号 */
+  column: number;
+  /** 原始标识符（sourcemap 中的 names），未记录时为 null */
+  name: string | null;
+  /** 命中 token 与查询列的列距，token 不在查询行时省略 */
+  column_distance?: number;
+  /** 所在原始函数名 */
+  function_name?: OriginalFunctionName;
+  /** 上下文源码行，仅传入 context_lines 且有 sourcesContent 时存在 */
+  context?: TokenSourceCodeLine[];
+  /** 目标行上映射表达式的范围，与 context 同时存在 */
+  span?: HighlightSpan;
+  /** 编译后代码摘录，仅传入 generated_source 时存在；缺少 sourcesContent 时作为回退上下文 */
+  generated?: GeneratedExcerpt;
+}
+
+/**
+ * 单个输入帧的映射结果，按 status 区分成功与失败。
+ */
+export type MappedStackFrame = {
+  /** 原始堆栈行 */
+  raw: string;
+  /** 帧的结构化属性 */
+  frame: FrameInfo;
+} & (
+  | ({ status: 'mapped' } & MappedLocation)
+  | { status: 'failed'; reason: FrameFailure }
+);
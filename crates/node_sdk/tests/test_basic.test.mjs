@@ -39,6 +39,57 @@ describe('node sdk basic exports', () => {
     expect(tok.source_code.length).toBeGreaterThanOrEqual(2);
     const target = tok.source_code.find((lny) => lny.is_stack_line);
     expect(target).toBeTruthy();
+    // 目标行与上下文行号均为 1-based
+    expect(tok.line).toBe(1);
+    expect(target.line).toBe(1);
+  });
+
+  it('try_lookup_token returns typed error object when lookup fails', () => {
+    const sm = simpleSM({ codeLines: ['fn()'] });
+    expect(JSON.parse(wasm.lookup_token(sm, 0, 0))).toBeNull();
+    const res = JSON.parse(wasm.try_lookup_token(sm, 0, 0));
+    expect(res.kind).toBe('line_zero');
+    expect(res.error).toContain('1-based');
+    // 最后一个映射之后的行仍返回 GLB token
+    expect(JSON.parse(wasm.lookup_token(sm, 5, 0)).src).toContain('src/a.js');
+  });
+
+  it('lookup_code_frame renders gutter and underline', () => {
+    const sm = simpleSM({ codeLines: ['l0()', 'l1()', 'l2()'] });
+    const frame = JSON.parse(wasm.lookup_code_frame(sm, 1, 0, 1, null));
+    expect(frame).toBe('> 1 | l0()\n    | ^^^^\n  2 | l1()');
+    const snippet = JSON.parse(wasm.lookup_context(sm, 1, 0, 1));
+    expect(snippet.span).toEqual({ start: 0, end: 4 });
+  });
+
+  it('lookup_context honours context_after and max_line_width', () => {
+    const sm = simpleSM({ codeLines: ['abcdefghij', 'l1()', 'l2()'] });
+    const snippet = JSON.parse(wasm.lookup_context(sm, 1, 0, 0, 1, 4));
+    expect(snippet.context.map((l) => l.code)).toEqual(['abcd…', 'l1()']);
+    expect(snippet.context[0].column_offset).toBe(0);
+    expect(snippet.span).toEqual({ start: 0, end: 4 });
+  });
+
+  it('falls back to generated code when sourcesContent is missing', () => {
+    const sm = JSON.stringify({
+      version: 3,
+      sources: ['src/a.js'],
+      names: [],
+      mappings: 'AAAA,SAAS,IAAI',
+    });
+    const generated = 'function add(a,b){return a+b}\n';
+    expect(JSON.parse(wasm.lookup_context(sm, 1, 9, 0))).toBeNull();
+    expect(JSON.parse(wasm.try_lookup_context(sm, 1, 9, 0)).kind).toBe('missing_source_content');
+    const snippet = JSON.parse(wasm.lookup_context(sm, 1, 9, 0, null, null, generated));
+    expect(snippet.context).toEqual([]);
+    expect(snippet.generated.span).toEqual({ start: 9, end: 13 });
+    const frame = JSON.parse(wasm.lookup_code_frame(sm, 1, 9, 0, null, null, null, generated));
+    expect(frame).toBe('> 1 | function add(a,b){return a+b}\n    |          ^^^^');
+    const mapped = JSON.parse(
+      wasm.map_error_stack(sm, 'Error: x\n    at f (https://a/min.js:1:10)', 0, null, null, generated)
+    );
+    expect(mapped.frames[0].context).toBeUndefined();
+    expect(mapped.frames[0].generated.lines[0].is_target).toBe(true);
   });
 
   it('lookup_context returns snippet', () => {
@@ -88,10 +139,9 @@ describe('node sdk basic exports', () => {
     ].join('\n');
     const raw = wasm.map_error_stack(sm, errorStackRaw, 1);
     const result = JSON.parse(raw);
-    expect(result.frames_with_context.length).toBe(1);
-    expect(
-      result.frames_with_context[0].source_code.length
-    ).toBeGreaterThanOrEqual(2);
+    expect(result.frames.length).toBe(1);
+    expect(result.frames[0].status).toBe('mapped');
+    expect(result.frames[0].context.length).toBe(2);
   });
 });
 
@@ -103,6 +153,13 @@ describe('batch token generation', () => {
     expect(tok.line).toBe(1);
   });
 
+  it('generate_token_by_single_stack keeps 0-based source_code lines in legacy mode', () => {
+    const sm = simpleSM({ codeLines: ['l0()', 'l1()', 'l2()'] });
+    const tok = JSON.parse(wasm.generate_token_by_single_stack(1, 0, sm, 1, true));
+    expect(tok.line).toBe(1);
+    expect(tok.source_code.find((l) => l.is_stack_line).line).toBe(0);
+  });
+
   it('generate_token_by_stack_raw with resolver + formatter', () => {
     const sm = simpleSM({ codeLines: ['l0()', 'l1()', 'l2()'] });
     const stackRaw = [
@@ -0,0 +1,127 @@
+use crate::context_window::HighlightSpan;
+use crate::position::utf16_to_byte_offset;
+
+/// 目标行标记与下划线
+const MARK_COLOR: &str = "31;1";
+/// 行号栏
+const GUTTER_COLOR: &str = "90";
+
+/// 代码帧渲染选项
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CodeFrameOptions {
+  /// 输出 ANSI 颜色 (目标行标记与下划线为红色, 行号栏为灰色)
+  pub color: bool,
+}
+
+/// 代码帧中的一行源码
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CodeFrameLine<'a> {
+  /// 1-based 行号
+  pub line: u32,
+  pub code: &'a str,
+  pub is_target: bool,
+}
+
+/// 渲染代码帧: 行号栏 + `>` 标记目标行, 目标行下方按 `span` 画出 `^` 下划线
+///
+/// ```text
+///   1 | function add(a, b) {
+/// > 2 |   return a.value + b;
+///     |          ^^^^^^^
+///   3 | }
+/// ```
+pub fn render_code_frame<'a>(
+  lines: impl IntoIterator<Item = CodeFrameLine<'a>>,
+  span: Option<HighlightSpan>,
+  options: CodeFrameOptions,
+) -> String {
+  let lines: Vec<_> = lines.into_iter().collect();
+  let width = lines
+    .iter()
+    .map(|l| l.line.to_string().len())
+    .max()
+    .unwrap_or(1);
+  let paint = |color: &str, text: &str| {
+    if options.color {
+      format!("\x1b[{color}m{text}\x1b[0m")
+    } else {
+      text.to_string()
+    }
+  };
+  let mut out = Vec::with_capacity(lines.len() + 1);
+  for l in &lines {
+    let marker = if l.is_target {
+      paint(MARK_COLOR, ">")
+    } else {
+      " ".to_string()
+    };
+    let gutter = paint(GUTTER_COLOR, &format!("{:>width$} |", l.line));
+    if l.code.is_empty() {
+      out.push(format!("{marker} {gutter}"));
+    } else {
+      out.push(format!("{marker} {gutter} {}", l.code));
+    }
+    if let (true, Some(span)) = (l.is_target, span) {
+      let start = utf16_to_byte_offset(l.code, span.start);
+      let end = utf16_to_byte_offset(l.code, span.end).max(start);
+      // 保留制表符, 使下划线与源码对齐
+      let padding: String = l.code[..start]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+      let carets = "^".repeat(l.code[start..end].chars().count().max(1));
+      let gutter = paint(GUTTER_COLOR, &format!("{:>width$} |", ""));
+      out.push(format!(
+        "  {gutter} {padding}{}",
+        paint(MARK_COLOR, &carets)
+      ));
+    }
+  }
+  out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lines<'a>(code: &[&'a str], target: u32) -> Vec<CodeFrameLine<'a>> {
+    code
+      .iter()
+      .zip(9..)
+      .map(|(code, line)| CodeFrameLine {
+        line,
+        code,
+        is_target: line == target,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn renders_gutter_marker_and_underline() {
+    let span = HighlightSpan { start: 9, end: 16 };
+    let frame = render_code_frame(
+      lines(&["fn()", "  return a.value + b;", ""], 10),
+      Some(span),
+      CodeFrameOptions::default(),
+    );
+    assert_eq!(
+      frame,
+      "   9 | fn()\n> 10 |   return a.value + b;\n     |          ^^^^^^^\n  11 |"
+    );
+  }
+
+  #[test]
+  fn underline_follows_tabs_and_utf16_columns() {
+    // "😀" 占 2 个 UTF-16 码元
+    let span = HighlightSpan { start: 4, end: 5 };
+    let frame = render_code_frame(
+      lines(&["\t😀 x"], 9),
+      Some(span),
+      CodeFrameOptions::default(),
+    );
+    assert_eq!(frame.lines().nth(1), Some("    | \t  ^"));
+
+    let colored = render_code_frame(lines(&["a"], 9), None, CodeFrameOptions { color: true });
+    assert_eq!(colored, "\x1b[31;1m>\x1b[0m \x1b[90m9 |\x1b[0m a");
+  }
+}
@@ -1,60 +1,158 @@
 use serde::Serialize;
 use sourcemap::SourceMap;
 
+use crate::code_frame::{render_code_frame, CodeFrameLine, CodeFrameOptions};
+use crate::context_window::{
+  context_window, mapping_span, truncate_line, ContextOptions, HighlightSpan, LineNumbering,
+};
+use crate::generated_code::GeneratedExcerpt;
+use crate::mapping_index::MappingIndex;
+use crate::token_generator::column_distance;
+
 #[derive(Clone, Debug, Serialize)]
 pub struct ContextLine {
+  /// 1-based 行号, [`LineNumbering::Legacy`] 下为 0-based
   pub line: u32,
   pub is_target: bool,
   pub code: String,
+  /// 行被截断时保留部分在原行中的起始列: `code` 去掉开头的 `…` 后第 i 个码元对应原行第 `column_offset + i` 列
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub column_offset: Option<u32>,
 }
 #[derive(Clone, Debug, Serialize)]
 pub struct ContextSnippet {
   pub src: String,
+  /// 目标行 (1-based)
   pub line: u32,
   pub column: u32,
+  /// 目标 token 在 `names` 中记录的原始标识符
+  pub name: Option<String>,
+  /// 命中 token 与查询列的列距, token 不在查询行时为 None
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub column_distance: Option<u32>,
+  /// 目标行上映射表达式的范围
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub span: Option<HighlightSpan>,
   pub context: Vec<ContextLine>,
+  /// 缺少 sourcesContent 时回退的编译后代码摘录 (`context` 为空)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub generated: Option<GeneratedExcerpt>,
+}
+
+impl ContextSnippet {
+  /// 将上下文渲染为带行号栏与高亮下划线的代码帧 (行号栏始终 1-based), 回退到编译后代码时渲染其摘录
+  pub fn code_frame(&self, options: CodeFrameOptions) -> String {
+    if let (true, Some(generated)) = (self.context.is_empty(), &self.generated) {
+      return generated.code_frame(options);
+    }
+    // Legacy 行号约定下 ContextLine.line 为 0-based, 以目标行换算
+    let offset = self
+      .context
+      .iter()
+      .find(|l| l.is_target)
+      .map_or(0, |t| self.line - t.line);
+    render_code_frame(
+      self.context.iter().map(|l| CodeFrameLine {
+        line: l.line + offset,
+        code: &l.code,
+        is_target: l.is_target,
+      }),
+      self.span,
+      options,
+    )
+  }
 }
 
 pub fn lookup_context_from_sourcemap(
   sourcemap: &SourceMap,
   compile_line: u32,
   compile_column: u32,
-  context_lines: u32,
+  context: impl Into<ContextOptions>,
+) -> Option<ContextSnippet> {
+  lookup_context_from_sourcemap_with_numbering(
+    sourcemap,
+    compile_line,
+    compile_column,
+    context,
+    LineNumbering::OneBased,
+  )
+}
+
+/// 同 [`lookup_context_from_sourcemap`], 按 `numbering` 输出 `ContextLine.line`
+pub fn lookup_context_from_sourcemap_with_numbering(
+  sourcemap: &SourceMap,
+  compile_line: u32,
+  compile_column: u32,
+  context: impl Into<ContextOptions>,
+  numbering: LineNumbering,
 ) -> Option<ContextSnippet> {
   if compile_line == 0 {
     return None;
   }
   let token = sourcemap.lookup_token(compile_line - 1, compile_column)?;
-  let origin_line = token.get_src_line() as u32;
-  let origin_col = token.get_src_col() as u32;
-  let src = token
-    .get_source()
-    .map(|s| s.to_string())
-    .unwrap_or_default();
+  context_snippet(
+    &MappingIndex::new(sourcemap),
+    &token,
+    compile_line,
+    compile_column,
+    context.into(),
+    numbering,
+  )
+}
+
+/// 以 token 的原始位置为中心截取上下文片段, 无 sourcesContent 时返回 None
+pub(crate) fn context_snippet(
+  index: &MappingIndex,
+  token: &sourcemap::Token<'_>,
+  compile_line: u32,
+  compile_column: u32,
+  options: ContextOptions,
+  numbering: LineNumbering,
+) -> Option<ContextSnippet> {
+  let (origin_line, origin_column) = (token.get_src_line(), token.get_src_col());
   let view = token.get_source_view()?;
-  let source_text = view.source();
-  let source_lines: Vec<&str> = source_text.lines().collect();
-  let start = origin_line.saturating_sub(context_lines);
-  let end = origin_line + context_lines;
-  let mut context: Vec<ContextLine> = Vec::new();
-  for ln in start..=end {
-    let code = source_lines
-      .get(ln as usize)
-      .cloned()
-      .unwrap_or("")
-      .to_string();
-    context.push(ContextLine {
-      line: ln,
-      is_target: ln == origin_line,
-      code,
-    });
+  let mut snippet = generated_snippet(token, compile_line, compile_column, None);
+  let mut target_kept = None;
+  let context = context_window(view.source(), origin_line, options.before, options.after)
+    .into_iter()
+    .map(|l| {
+      let (code, kept) = truncate_line(l.code, origin_column, options.max_line_width);
+      if l.is_target {
+        target_kept = kept;
+      }
+      ContextLine {
+        line: numbering.number(l.line),
+        is_target: l.is_target,
+        code: code.into_owned(),
+        column_offset: kept.map(|(from, _)| from),
+      }
+    })
+    .collect();
+  snippet.span = mapping_span(index, token).map(|s| s.within(target_kept));
+  snippet.context = context;
+  Some(snippet)
+}
+
+/// 不含原始上下文的片段, 缺少 sourcesContent 时以编译后代码摘录回退
+pub(crate) fn generated_snippet(
+  token: &sourcemap::Token<'_>,
+  compile_line: u32,
+  compile_column: u32,
+  generated: Option<GeneratedExcerpt>,
+) -> ContextSnippet {
+  ContextSnippet {
+    src: token
+      .get_source()
+      .map(|s| s.to_string())
+      .unwrap_or_default(),
+    line: token.get_src_line() + 1,
+    column: token.get_src_col(),
+    name: token.get_name().map(|s| s.to_string()),
+    column_distance: column_distance(token, compile_line, compile_column),
+    span: None,
+    context: Vec::new(),
+    generated,
   }
-  Some(ContextSnippet {
-    src,
-    line: origin_line + 1,
-    column: origin_col,
-    context,
-  })
 }
 
 #[cfg(test)]
@@ -70,8 +168,16 @@ mod tests {
   fn lookup_basic_context() {
     let smap = sm("l0()\nl1()\nl2()\n");
     let snippet = lookup_context_from_sourcemap(&smap, 1, 0, 1).unwrap();
-    assert!(snippet.context.len() >= 2);
-    assert!(snippet.context.iter().any(|c| c.is_target));
+    assert_eq!(snippet.line, 1);
+    assert_eq!(snippet.context.len(), 2);
+    assert_eq!(
+      (snippet.context[0].line, snippet.context[0].is_target),
+      (1, true)
+    );
+    let legacy =
+      lookup_context_from_sourcemap_with_numbering(&smap, 1, 0, 1, LineNumbering::Legacy).unwrap();
+    assert_eq!(legacy.line, 1);
+    assert_eq!(legacy.context[0].line, 0);
   }
   #[test]
   fn lookup_returns_none_when_line_zero() {
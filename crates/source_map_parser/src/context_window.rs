@@ -0,0 +1,238 @@
+use serde::Serialize;
+use std::borrow::Cow;
+
+use crate::mapping_index::MappingIndex;
+use crate::position::{byte_to_utf16_offset, utf16_to_byte_offset};
+
+/// 截断行时标记被省略部分
+const ELLIPSIS: char = '…';
+
+/// 上下文截取参数; 由 `u32` 转换时前后取相同行数且不截断行宽
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContextOptions {
+  /// 目标行之前的行数
+  pub before: u32,
+  /// 目标行之后的行数
+  pub after: u32,
+  /// 单行最大宽度 (UTF-16 码元), 超出时围绕目标列保留该宽度, 被省略的一侧以 `…` 标记; 0 视为不截断
+  pub max_line_width: Option<u32>,
+}
+
+impl ContextOptions {
+  pub fn new(before: u32, after: u32) -> Self {
+    ContextOptions {
+      before,
+      after,
+      max_line_width: None,
+    }
+  }
+
+  pub fn with_max_line_width(mut self, max_line_width: u32) -> Self {
+    self.max_line_width = Some(max_line_width);
+    self
+  }
+}
+
+impl From<u32> for ContextOptions {
+  fn from(lines: u32) -> Self {
+    ContextOptions::new(lines, lines)
+  }
+}
+
+/// 上下文 API 返回的行号约定
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineNumbering {
+  /// 所有行号 (目标行与各上下文行) 均为 1-based, 与编辑器显示一致
+  #[default]
+  OneBased,
+  /// 兼容旧版输出: `ContextLine.line`、`lookup_token_with_context` 返回的 `Token.line` 与
+  /// `get_stack_source` / `GenerateToken` 返回的 `SourceCode.line` 为 0-based, 其余行号不变
+  Legacy,
+}
+
+impl LineNumbering {
+  /// 0-based 行号 -> 本约定下 `ContextLine.line` / `Token.line` 的值
+  pub(crate) fn number(self, line: u32) -> u32 {
+    match self {
+      LineNumbering::OneBased => line + 1,
+      LineNumbering::Legacy => line,
+    }
+  }
+}
+
+/// 目标行上映射表达式的范围: 0-based UTF-16 列, 左闭右开 (与 JS `String.prototype.slice` 一致),
+/// 不受 [`crate::position::ColumnBase`] 影响
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct HighlightSpan {
+  pub start: u32,
+  pub end: u32,
+}
+
+impl HighlightSpan {
+  /// 换算到截断后的显示文本, `kept` 为 [`truncate_line`] 保留部分在原行中的列范围
+  pub(crate) fn within(self, kept: Option<(u32, u32)>) -> Self {
+    let Some((from, to)) = kept else {
+      return self;
+    };
+    let lead = u32::from(from > 0);
+    let shift = |column: u32| column.clamp(from, to) - from + lead;
+    HighlightSpan {
+      start: shift(self.start),
+      end: shift(self.end),
+    }
+  }
+}
+
+/// token 在原始行上覆盖的范围: 从 token 列到同一原始行上下一个映射的列,
+/// 其后没有映射时到行尾 (不含尾部空白); 缺少 sourcesContent 时为 None
+pub(crate) fn mapping_span(
+  index: &MappingIndex,
+  token: &sourcemap::Token<'_>,
+) -> Option<HighlightSpan> {
+  let view = token.get_source_view()?;
+  let (src_id, line, start) = (
+    token.get_src_id(),
+    token.get_src_line(),
+    token.get_src_col(),
+  );
+  let next = index.next_original_column(src_id, line, start);
+  let end = next.unwrap_or_else(|| {
+    view.get_line(line).map_or(start, |code| {
+      byte_to_utf16_offset(code, code.trim_end().len())
+    })
+  });
+  Some(HighlightSpan {
+    start,
+    end: end.max(start),
+  })
+}
+
+/// 行宽超过 `max_width` 时围绕 `column` 截取, 返回显示文本与保留部分在原行中的列范围 (未截断时为 None);
+/// `max_width` 为 0 时不截断
+pub(crate) fn truncate_line(
+  code: &str,
+  column: u32,
+  max_width: Option<u32>,
+) -> (Cow<'_, str>, Option<(u32, u32)>) {
+  let len = byte_to_utf16_offset(code, code.len());
+  let width = match max_width {
+    Some(w) if w > 0 && len > w => w,
+    _ => return (Cow::Borrowed(code), None),
+  };
+  let start = column.saturating_sub(width / 2).min(len - width);
+  let from = utf16_to_byte_offset(code, start);
+  let to = utf16_to_byte_offset(code, start + width);
+  let mut out = String::with_capacity(to - from + 2 * ELLIPSIS.len_utf8());
+  if from > 0 {
+    out.push(ELLIPSIS);
+  }
+  out.push_str(&code[from..to]);
+  if to < code.len() {
+    out.push(ELLIPSIS);
+  }
+  // 代理对边界可能使起止列前移
+  let kept = (
+    byte_to_utf16_offset(code, from),
+    byte_to_utf16_offset(code, to),
+  );
+  (Cow::Owned(out), Some(kept))
+}
+
+/// 上下文窗口中的一行
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct WindowLine<'a> {
+  /// 0-based 行号
+  pub line: u32,
+  pub code: &'a str,
+  pub is_target: bool,
+}
+
+/// 截取 `target` (0-based) 行及其前 `before` 行、后 `after` 行 (两端均包含)
+///
+/// 窗口截断到源码实际行数内; 目标行超出源码时仍保留为空行
+pub(crate) fn context_window(
+  source: &str,
+  target: u32,
+  before: u32,
+  after: u32,
+) -> Vec<WindowLine<'_>> {
+  let lines: Vec<&str> = source.lines().collect();
+  let last = (lines.len() as u32).saturating_sub(1).max(target);
+  let start = target.saturating_sub(before);
+  let end = target.saturating_add(after).min(last);
+  (start..=end)
+    .map(|line| WindowLine {
+      line,
+      code: lines.get(line as usize).copied().unwrap_or(""),
+      is_target: line == target,
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use sourcemap::SourceMap;
+
+  #[test]
+  fn window_is_inclusive_and_clamped() {
+    let src = "l0\nl1\nl2\nl3\n";
+    let w = context_window(src, 1, 1, 1);
+    let lines: Vec<_> = w.iter().map(|l| (l.line, l.code, l.is_target)).collect();
+    assert_eq!(
+      lines,
+      vec![(0, "l0", false), (1, "l1", true), (2, "l2", false)]
+    );
+    // 文件头尾截断, 不补空行
+    assert_eq!(context_window(src, 0, 5, 0).len(), 1);
+    assert_eq!(context_window(src, 3, 0, 5).len(), 1);
+    let w = context_window(src, 2, 0, 9);
+    assert_eq!(w.last().unwrap().code, "l3");
+    // 目标行越界
+    let w = context_window(src, 6, 1, 1);
+    assert_eq!((w.len(), w[1].code, w[1].is_target), (2, "", true));
+    assert_eq!(LineNumbering::Legacy.number(3), 3);
+    assert_eq!(LineNumbering::OneBased.number(3), 4);
+  }
+
+  #[test]
+  fn truncate_keeps_window_around_column() {
+    let code = "0123456789abcdefghij";
+    assert_eq!(truncate_line(code, 10, None).1, None);
+    assert_eq!(truncate_line(code, 10, Some(20)).1, None);
+    assert_eq!(truncate_line(code, 10, Some(0)).1, None);
+    let (text, kept) = truncate_line(code, 10, Some(6));
+    assert_eq!((text.as_ref(), kept), ("…789abc…", Some((7, 13))));
+    // 靠近行首 / 行尾时窗口贴边, 只在一侧加省略号
+    let (text, kept) = truncate_line(code, 1, Some(6));
+    assert_eq!((text.as_ref(), kept), ("012345…", Some((0, 6))));
+    let (text, kept) = truncate_line(code, 19, Some(6));
+    assert_eq!((text.as_ref(), kept), ("…efghij", Some((14, 20))));
+
+    let span = HighlightSpan { start: 10, end: 16 };
+    assert_eq!(
+      span.within(Some((7, 13))),
+      HighlightSpan { start: 4, end: 7 }
+    );
+    assert_eq!(span.within(None), span);
+  }
+
+  #[test]
+  fn span_ends_at_next_mapping_or_line_end() {
+    // 原始第 1 行: 第 2 列 -> 第 6 列 -> 第 10 列, 第 2 行第 0 列
+    let sm = SourceMap::from_reader(
+      br#"{"version":3,"sources":["a.js"],"sourcesContent":["a = foo(bar);  \nb"],"names":[],"mappings":"AAAE,CAAI,CAAI,CACV"}"#
+        .as_slice(),
+    )
+    .unwrap();
+    let index = MappingIndex::new(&sm);
+    let spans: Vec<_> = sm
+      .tokens()
+      .map(|t| mapping_span(&index, &t).map(|s| (s.start, s.end)))
+      .collect();
+    assert_eq!(
+      spans,
+      vec![Some((2, 6)), Some((6, 10)), Some((10, 13)), Some((0, 1))]
+    );
+  }
+}
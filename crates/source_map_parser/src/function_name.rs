@@ -0,0 +1,172 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::generated_code::JsLexer;
+use crate::position::utf16_to_byte_offset;
+
+/// 函数头匹配规则 (均锚定在 `{` 之前的文本末尾), 捕获组 `name` 为函数名
+static FUNCTION_HEADER_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+  vec![
+    // function foo(a, b) {  /  async function* foo(a): T {
+    Regex::new(r"function\s*\*?\s*(?P<name>[A-Za-z_$][\w$]*)\s*(?:<[^(){}]*>)?\s*\([^(){}]*\)(?:\s*:\s*[^(){}=]+)?\s*$")
+      .unwrap(),
+    // foo = function (a) {  /  foo: async function bar() {
+    Regex::new(r"(?P<name>[A-Za-z_$][\w$]*)\s*[:=]\s*(?:async\s+)?function\b\s*\*?\s*(?:[A-Za-z_$][\w$]*)?\s*\([^(){}]*\)(?:\s*:\s*[^(){}=]+)?\s*$")
+      .unwrap(),
+    // const foo = (a) => {  /  foo: async x => {
+    Regex::new(r"(?P<name>[A-Za-z_$][\w$]*)\s*(?::\s*[^(){}=]+)?\s*[:=]\s*(?:async\s+)?(?:\([^(){}]*\)(?:\s*:\s*[^(){}=]+)?|[A-Za-z_$][\w$]*)\s*=>\s*$")
+      .unwrap(),
+    // class / 对象方法: foo(a) {  /  static async *foo(a): T {
+    Regex::new(r"(?:^|[\s;{},])(?:(?:static|async|get|set|public|private|protected)\s+)*\*?\s*(?P<name>[A-Za-z_$][\w$]*)\s*\([^(){}]*\)(?:\s*:\s*[^(){}=]+)?\s*$")
+      .unwrap(),
+  ]
+});
+
+/// 匿名函数头: 命中后停止向外查找 (所在函数确实没有名字)
+static ANONYMOUS_HEADER: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r"(?:function\s*\*?\s*\([^(){}]*\)|(?:^|[(,\s])(?:async\s+)?\([^(){}]*\)\s*=>)\s*$")
+    .unwrap()
+});
+
+/// 形似方法调用但实际是控制流的关键字
+const NON_FUNCTION_KEYWORDS: &[&str] = &[
+  "if", "for", "while", "switch", "catch", "with", "function", "return",
+];
+
+/// 向前查看函数头时的最大字节数
+const HEADER_LOOKBEHIND: usize = 512;
+
+/// 原始函数名的推断方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FunctionNameSource {
+  /// 取自 Hermes / Metro map 的 `x_facebook_sources` 函数映射
+  FunctionMap,
+  /// 从 `sourcesContent` 中向前扫描最近的函数声明
+  SourceScan,
+  /// 取调用方帧调用点 token 的 `names` 条目
+  CallSite,
+}
+
+/// 推断出的原始函数名
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct OriginalFunctionName {
+  pub name: String,
+  pub source: FunctionNameSource,
+}
+
+/// 在原始源码中, 从 (0-based 行, 列) 向外查找包裹该位置的最近具名函数
+///
+/// 基于括号配对的启发式扫描, 字符串、模板、正则字面量与注释中的括号不计入; 遇到匿名函数时返回 None
+pub fn scan_enclosing_function_name(source: &str, line: u32, column: u32) -> Option<String> {
+  let offset = byte_offset(source, line, column)?;
+  // 从文件开头正向扫描到目标位置, 记录仍未闭合的 `{`
+  let mut lexer = JsLexer::default();
+  let mut open = Vec::new();
+  let mut chars = source[..offset].char_indices().peekable();
+  while let Some((i, c)) = chars.next() {
+    if !lexer.step(c, chars.peek().map(|&(_, n)| n), &source[..i]) {
+      continue;
+    }
+    match c {
+      '{' => open.push(i),
+      '}' => {
+        open.pop();
+      }
+      _ => {}
+    }
+  }
+  for &pos in open.iter().rev() {
+    let mut start = pos.saturating_sub(HEADER_LOOKBEHIND);
+    while !source.is_char_boundary(start) {
+      start += 1;
+    }
+    let header = &source[start..pos];
+    if let Some(name) = match_function_header(header) {
+      return Some(name);
+    }
+    if ANONYMOUS_HEADER.is_match(header) {
+      return None;
+    }
+  }
+  None
+}
+
+fn match_function_header(header: &str) -> Option<String> {
+  for re in FUNCTION_HEADER_PATTERNS.iter() {
+    if let Some(caps) = re.captures(header) {
+      let name = caps.name("name")?.as_str();
+      if !NON_FUNCTION_KEYWORDS.contains(&name) {
+        return Some(name.to_string());
+      }
+    }
+  }
+  None
+}
+
+/// (0-based 行, UTF-16 列) -> 字节偏移, 列超出行尾时截断到行尾
+fn byte_offset(source: &str, line: u32, column: u32) -> Option<usize> {
+  let mut line_start = 0usize;
+  for _ in 0..line {
+    line_start += source[line_start..].find('\n')? + 1;
+  }
+  let line_text = source[line_start..].split('\n').next().unwrap_or("");
+  Some(line_start + utf16_to_byte_offset(line_text, column))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn scan_function_declaration() {
+    let src = "function outer(a) {\n  if (a) {\n    throw new Error(a);\n  }\n}\n";
+    assert_eq!(
+      scan_enclosing_function_name(src, 2, 4).as_deref(),
+      Some("outer")
+    );
+  }
+
+  #[test]
+  fn scan_arrow_and_method() {
+    let src = "const handleClick = (e) => {\n  e.preventDefault();\n};\nclass A {\n  render() {\n    return x;\n  }\n}\n";
+    assert_eq!(
+      scan_enclosing_function_name(src, 1, 2).as_deref(),
+      Some("handleClick")
+    );
+    assert_eq!(
+      scan_enclosing_function_name(src, 5, 4).as_deref(),
+      Some("render")
+    );
+  }
+
+  #[test]
+  fn scan_skips_sibling_blocks_and_stops_at_anonymous() {
+    let src = "function a() {\n  function b() { x(); }\n  y();\n}\nlist.forEach(function (v) {\n  z(v);\n});\n";
+    assert_eq!(
+      scan_enclosing_function_name(src, 2, 2).as_deref(),
+      Some("a")
+    );
+    assert_eq!(scan_enclosing_function_name(src, 5, 2), None);
+  }
+
+  #[test]
+  fn scan_ignores_braces_in_strings_and_comments() {
+    let src = "function outer() {\n  const s = \"}\";\n  const t = `${a} }`;\n  const r = /}/;\n  // }\n  /* } */\n  boom();\n}\n";
+    assert_eq!(
+      scan_enclosing_function_name(src, 6, 2).as_deref(),
+      Some("outer")
+    );
+    let src = "function a() {}\nconst s = '{';\nfunction b() {\n  x();\n}\n";
+    assert_eq!(
+      scan_enclosing_function_name(src, 3, 2).as_deref(),
+      Some("b")
+    );
+  }
+
+  #[test]
+  fn scan_top_level_returns_none() {
+    assert_eq!(scan_enclosing_function_name("run();\n", 0, 0), None);
+  }
+}
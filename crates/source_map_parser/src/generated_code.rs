@@ -0,0 +1,413 @@
+use serde::Serialize;
+
+use crate::code_frame::{render_code_frame, CodeFrameLine, CodeFrameOptions};
+use crate::context_window::{context_window, truncate_line, ContextOptions, HighlightSpan};
+use crate::position::{byte_to_utf16_offset, utf16_to_byte_offset};
+
+/// 目标行不超过该宽度 (UTF-16 码元) 时按原样截取前后行, 超过时视为压缩代码做美化
+const MAX_PLAIN_WIDTH: u32 = 160;
+/// 美化时截取查询列前后各多少码元的代码
+const BEAUTIFY_RADIUS: u32 = 400;
+const INDENT: &str = "  ";
+
+/// 编译后代码在查询位置附近的摘录
+#[derive(Clone, Debug, Serialize)]
+pub struct GeneratedExcerpt {
+  /// 编译后行号 (1-based), 同查询
+  pub line: u32,
+  /// 编译后列号, 同查询
+  pub column: u32,
+  /// 目标行过长 (压缩代码) 时截取查询列附近的代码, 按语句拆行并缩进
+  pub beautified: bool,
+  pub lines: Vec<GeneratedLine>,
+  /// 查询列到同一编译后行下一个映射之间的代码, 为目标摘录行内的 0-based UTF-16 列
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub span: Option<HighlightSpan>,
+}
+
+/// 摘录中的一行
+#[derive(Clone, Debug, Serialize)]
+pub struct GeneratedLine {
+  /// 所在编译后行号 (1-based); 美化拆出的各行来自同一编译后行
+  pub line: u32,
+  pub is_target: bool,
+  pub code: String,
+}
+
+impl GeneratedExcerpt {
+  /// 渲染为带行号栏与高亮下划线的代码帧
+  pub fn code_frame(&self, options: CodeFrameOptions) -> String {
+    render_code_frame(
+      self.lines.iter().map(|l| CodeFrameLine {
+        line: l.line,
+        code: &l.code,
+        is_target: l.is_target,
+      }),
+      self.span,
+      options,
+    )
+  }
+}
+
+/// 截取编译后代码 `line` 行 `column` 列 (均 0-based) 附近的摘录, `span_end` 为同一行下一个映射的列;
+/// 行号超出文件时为 None
+pub(crate) fn generated_excerpt(
+  source: &str,
+  line: u32,
+  column: u32,
+  span_end: Option<u32>,
+  options: ContextOptions,
+) -> Option<GeneratedExcerpt> {
+  let code = source.lines().nth(line as usize)?;
+  let len = byte_to_utf16_offset(code, code.len());
+  let start = column.min(len);
+  let span = HighlightSpan {
+    start,
+    end: span_end.unwrap_or(column + 1).min(len).max(start),
+  };
+  let (lines, span, beautified) = if len <= MAX_PLAIN_WIDTH {
+    plain_lines(source, line, span, options)
+  } else {
+    beautified_lines(code, line, span, options)
+  };
+  Some(GeneratedExcerpt {
+    line: line + 1,
+    column,
+    beautified,
+    lines,
+    span: Some(span),
+  })
+}
+
+/// 未压缩的代码: 与原始上下文一样按行截取
+fn plain_lines(
+  source: &str,
+  line: u32,
+  span: HighlightSpan,
+  options: ContextOptions,
+) -> (Vec<GeneratedLine>, HighlightSpan, bool) {
+  let mut target_kept = None;
+  let lines = context_window(source, line, options.before, options.after)
+    .into_iter()
+    .map(|l| {
+      let (code, kept) = truncate_line(l.code, span.start, options.max_line_width);
+      if l.is_target {
+        target_kept = kept;
+      }
+      GeneratedLine {
+        line: l.line + 1,
+        is_target: l.is_target,
+        code: code.into_owned(),
+      }
+    })
+    .collect();
+  (lines, span.within(target_kept), false)
+}
+
+/// 压缩代码: 截取查询列附近的一段美化, 再以目标语句所在行为中心取前后行
+fn beautified_lines(
+  code: &str,
+  line: u32,
+  span: HighlightSpan,
+  options: ContextOptions,
+) -> (Vec<GeneratedLine>, HighlightSpan, bool) {
+  let from = utf16_to_byte_offset(code, span.start.saturating_sub(BEAUTIFY_RADIUS));
+  let to = utf16_to_byte_offset(code, span.start.saturating_add(BEAUTIFY_RADIUS));
+  let marks = [
+    utf16_to_byte_offset(code, span.start) - from,
+    utf16_to_byte_offset(code, span.end).min(to) - from,
+  ];
+  let (mut out, pos) = beautify(&code[from..to], &marks);
+  if from > 0 {
+    out[0].insert(0, '…');
+  }
+  if to < code.len() {
+    out.last_mut().unwrap().push('…');
+  }
+  let (target, start) = pos[0];
+  let shift = u32::from(from > 0 && target == 0);
+  let start = start + shift;
+  let end = match pos[1] {
+    (l, c) if l == target => c + shift,
+    _ => byte_to_utf16_offset(&out[target], out[target].len()),
+  };
+  let first = target.saturating_sub(options.before as usize);
+  let last = target
+    .saturating_add(options.after as usize)
+    .min(out.len() - 1);
+  let mut target_kept = None;
+  let lines = (first..=last)
+    .map(|i| {
+      let column = if i == target { start } else { 0 };
+      let (code, kept) = truncate_line(&out[i], column, options.max_line_width);
+      if i == target {
+        target_kept = kept;
+      }
+      GeneratedLine {
+        line: line + 1,
+        is_target: i == target,
+        code: code.into_owned(),
+      }
+    })
+    .collect();
+  let span = HighlightSpan { start, end }.within(target_kept);
+  (lines, span, true)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum State {
+  #[default]
+  Code,
+  Str(char),
+  LineComment,
+  /// 块注释, 参数为上一个字符是否为 `*` (不含开头的 `/*`)
+  BlockComment(bool),
+  /// 正则字面量, 参数为是否位于字符类 `[...]` 内
+  Regex(bool),
+}
+
+/// 逐字符区分代码与字符串、模板、正则字面量及注释的简易 JS 词法状态机
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct JsLexer {
+  state: State,
+  escaped: bool,
+  /// 刚进入块注释, 下一个字符是开头的 `*`
+  comment_opening: bool,
+}
+
+impl JsLexer {
+  /// 消费字符 `c`, `next` 为其后的字符, `before` 为 `c` 之前的代码 (用于判断 `/` 是否开始正则);
+  /// 返回 `c` 是否为代码, 字符串、正则与注释的内容及其定界符均不是
+  pub(crate) fn step(&mut self, c: char, next: Option<char>, before: &str) -> bool {
+    match self.state {
+      State::Str(quote) => {
+        if self.escaped {
+          self.escaped = false;
+        } else if c == '\\' {
+          self.escaped = true;
+        } else if c == quote {
+          self.state = State::Code;
+        }
+        false
+      }
+      State::LineComment => {
+        if c == '\n' {
+          self.state = State::Code;
+          return true;
+        }
+        false
+      }
+      State::Regex(in_class) => {
+        if self.escaped {
+          self.escaped = false;
+        } else if c == '\\' {
+          self.escaped = true;
+        } else if c == '[' || (c == ']' && in_class) {
+          self.state = State::Regex(c == '[');
+        } else if c == '/' && !in_class {
+          self.state = State::Code;
+        }
+        false
+      }
+      State::BlockComment(star) => {
+        if std::mem::take(&mut self.comment_opening) {
+          return false;
+        }
+        self.state = if c == '/' && star {
+          State::Code
+        } else {
+          State::BlockComment(c == '*')
+        };
+        false
+      }
+      State::Code => {
+        self.state = match (c, next) {
+          ('"' | '\'' | '`', _) => State::Str(c),
+          ('/', Some('/')) => State::LineComment,
+          ('/', Some('*')) => {
+            self.comment_opening = true;
+            State::BlockComment(false)
+          }
+          ('/', _) if starts_regex(before) => State::Regex(false),
+          _ => return true,
+        };
+        false
+      }
+    }
+  }
+}
+
+/// `/` 出现在这些字符或关键字之后时为正则字面量而非除号
+///
+/// 不含 `*`: 截取的片段可能从块注释中间开始, `*/` 不能当作正则的开头
+fn starts_regex(before: &str) -> bool {
+  let before = before.trim_end();
+  match before.chars().last() {
+    None => true,
+    // `i++/2` 为除法
+    Some('+' | '-') if before.ends_with("++") || before.ends_with("--") => false,
+    Some(c) if "(,=:[!&|?{};+-%<>~^".contains(c) => true,
+    Some(_) => ["return", "typeof", "case", "do", "else", "in", "of", "void"]
+      .iter()
+      .any(|k| {
+        before.ends_with(k)
+          && !before[..before.len() - k.len()]
+            .ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '$')
+      }),
+  }
+}
+
+/// 按语句拆行并按花括号缩进, 字符串、模板、正则字面量与注释原样保留
+///
+/// 返回输出行, 以及各 `marks` 字节偏移在输出中的 (行下标, UTF-16 列)
+fn beautify(code: &str, marks: &[usize]) -> (Vec<String>, Vec<(usize, u32)>) {
+  let mut lines: Vec<String> = Vec::new();
+  let mut cur = String::new();
+  let mut pos = vec![(0, 0); marks.len()];
+  let (mut depth, mut parens) = (0usize, 0usize);
+  let mut lexer = JsLexer::default();
+  let newline = |lines: &mut Vec<String>, cur: &mut String, depth: usize| {
+    if !cur.trim().is_empty() {
+      lines.push(std::mem::take(cur));
+    }
+    *cur = INDENT.repeat(depth);
+  };
+  let mut chars = code.char_indices().peekable();
+  while let Some((i, c)) = chars.next() {
+    for (m, p) in marks.iter().zip(pos.iter_mut()) {
+      if *m == i {
+        // 行首的空白会被丢弃, 标记落在缩进之后
+        *p = (lines.len(), byte_to_utf16_offset(&cur, cur.len()));
+      }
+    }
+    // 同一行内已输出的代码决定 `/` 的含义; 行首 (上一行以 `{` `;` 结束) 也是正则
+    if !lexer.step(c, chars.peek().map(|&(_, n)| n), &cur) {
+      cur.push(c);
+      continue;
+    }
+    match c {
+      c if c.is_whitespace() => {
+        if !cur.trim().is_empty() {
+          cur.push(c);
+        }
+      }
+      '{' => {
+        cur.push(c);
+        depth += 1;
+        newline(&mut lines, &mut cur, depth);
+      }
+      '}' => {
+        depth = depth.saturating_sub(1);
+        newline(&mut lines, &mut cur, depth);
+        cur.push(c);
+        // `},` `})` `};` 等保持在同一行
+        if !matches!(chars.peek(), Some((_, ';' | ',' | ')' | ']' | '.'))) {
+          newline(&mut lines, &mut cur, depth);
+        }
+      }
+      ';' => {
+        cur.push(c);
+        if parens == 0 {
+          newline(&mut lines, &mut cur, depth);
+        }
+      }
+      '(' | '[' => {
+        parens += 1;
+        cur.push(c);
+      }
+      ')' | ']' => {
+        parens = parens.saturating_sub(1);
+        cur.push(c);
+      }
+      _ => cur.push(c),
+    }
+  }
+  for (m, p) in marks.iter().zip(pos.iter_mut()) {
+    if *m >= code.len() {
+      *p = (lines.len(), byte_to_utf16_offset(&cur, cur.len()));
+    }
+  }
+  if !cur.trim().is_empty() || lines.is_empty() {
+    lines.push(cur);
+  }
+  // 标记位于被丢弃的末尾空行时指向最后一行
+  let last = lines.len() - 1;
+  for p in &mut pos {
+    if p.0 > last {
+      *p = (last, byte_to_utf16_offset(&lines[last], lines[last].len()));
+    }
+  }
+  (lines, pos)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn beautify_splits_statements_and_tracks_marks() {
+    let code = r#"function f(a){if(a){return "x;{}"}for(;;){g()}}var b=1;"#;
+    let at = code.find("return").unwrap();
+    let (lines, pos) = beautify(code, &[at, code.find("for").unwrap()]);
+    assert_eq!(
+      lines,
+      vec![
+        "function f(a){",
+        "  if(a){",
+        "    return \"x;{}\"",
+        "  }",
+        "  for(;;){",
+        "    g()",
+        "  }",
+        "}",
+        "var b=1;",
+      ]
+    );
+    assert_eq!(pos, vec![(2, 4), (4, 2)]);
+
+    // 正则字面量中的括号与分号不参与拆行, 除号照常处理
+    let code = r#"var r=/[{;}]\//g;if(a){x=b/2;z=i++/2;y=c}"#;
+    let (lines, _) = beautify(code, &[]);
+    assert_eq!(
+      lines,
+      vec![
+        r#"var r=/[{;}]\//g;"#,
+        "if(a){",
+        "  x=b/2;",
+        "  z=i++/2;",
+        "  y=c",
+        "}"
+      ]
+    );
+  }
+
+  #[test]
+  fn excerpt_beautifies_long_lines_only() {
+    let source = "a();\nb();\nc();\n";
+    let ex = generated_excerpt(source, 1, 0, Some(1), ContextOptions::from(1)).unwrap();
+    assert!(!ex.beautified);
+    let lines: Vec<_> = ex.lines.iter().map(|l| (l.line, l.code.as_str())).collect();
+    assert_eq!(lines, vec![(1, "a();"), (2, "b();"), (3, "c();")]);
+    assert_eq!(ex.span, Some(HighlightSpan { start: 0, end: 1 }));
+    assert!(generated_excerpt(source, 5, 0, None, 0.into()).is_none());
+
+    let long = format!("{}function boom(){{throw new Error(1)}}", "x();".repeat(60));
+    let column = long.find("throw").unwrap() as u32;
+    let ex = generated_excerpt(
+      &long,
+      0,
+      column,
+      Some(column + 9),
+      ContextOptions::new(1, 1),
+    )
+    .unwrap();
+    assert!(ex.beautified);
+    let lines: Vec<_> = ex.lines.iter().map(|l| l.code.as_str()).collect();
+    assert_eq!(lines, vec!["function boom(){", "  throw new Error(1)", "}"]);
+    assert_eq!(ex.span, Some(HighlightSpan { start: 2, end: 11 }));
+    assert_eq!(
+      ex.code_frame(CodeFrameOptions::default()),
+      "  1 | function boom(){\n> 1 |   throw new Error(1)\n    |   ^^^^^^^^^\n  1 | }"
+    );
+  }
+}
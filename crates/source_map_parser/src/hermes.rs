@@ -0,0 +1,143 @@
+use serde::Deserialize;
+use sourcemap::vlq::parse_vlq_segment;
+use std::collections::HashMap;
+
+#[derive(Deserialize)]
+struct RawHermesExtensions {
+  #[serde(default)]
+  x_facebook_sources: Option<Vec<Option<Vec<RawFunctionMap>>>>,
+  #[serde(default)]
+  x_hermes_function_offsets: Option<HashMap<String, Vec<u32>>>,
+}
+
+#[derive(Deserialize)]
+struct RawFunctionMap {
+  names: Vec<String>,
+  mappings: String,
+}
+
+/// 单个 source 的 Metro 函数映射: 各函数作用域起点 (1-based 行, 列, 名称下标), 按位置升序
+struct FunctionMap {
+  names: Vec<String>,
+  scopes: Vec<(u32, u32, usize)>,
+}
+
+impl FunctionMap {
+  /// 与 metro-symbolicate 一致: 每段为 (列增量, 名称下标增量, 行增量), 列在每个 `;` 后归零
+  fn decode(raw: &RawFunctionMap) -> Option<Self> {
+    let mut scopes = Vec::new();
+    let mut line: i64 = 1;
+    let mut name_index: i64 = 0;
+    for line_mappings in raw.mappings.split(';') {
+      let mut column: i64 = 0;
+      for segment in line_mappings.split(',').filter(|s| !s.is_empty()) {
+        let nums = parse_vlq_segment(segment).ok()?;
+        column += *nums.first()?;
+        name_index += nums.get(1).copied().unwrap_or(0);
+        line += nums.get(2).copied().unwrap_or(0);
+        scopes.push((
+          u32::try_from(line).ok()?,
+          u32::try_from(column).ok()?,
+          usize::try_from(name_index).ok()?,
+        ));
+      }
+    }
+    scopes.sort();
+    Some(FunctionMap {
+      names: raw.names.clone(),
+      scopes,
+    })
+  }
+}
+
+/// React Native / Hermes source map 的 Facebook 扩展字段
+///
+/// - `x_facebook_sources`: Metro 生成的函数映射, 用于还原帧所在的原始函数名
+/// - `x_hermes_function_offsets`: 字节码各函数的起始虚拟偏移, 用于 (函数 id, 字节码偏移) 定位
+pub struct HermesMetadata {
+  function_maps: Vec<Option<FunctionMap>>,
+  function_offsets: HashMap<u32, Vec<u32>>,
+}
+
+impl HermesMetadata {
+  /// 从 source map JSON 中提取扩展字段, 两者都不存在时返回 None
+  pub fn from_slice(sourcemap_content: &[u8]) -> Option<Self> {
+    let raw: RawHermesExtensions = serde_json::from_slice(sourcemap_content).ok()?;
+    if raw.x_facebook_sources.is_none() && raw.x_hermes_function_offsets.is_none() {
+      return None;
+    }
+    let function_maps = raw
+      .x_facebook_sources
+      .unwrap_or_default()
+      .iter()
+      .map(|entry| FunctionMap::decode(entry.as_ref()?.first()?))
+      .collect();
+    let function_offsets = raw
+      .x_hermes_function_offsets
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|(segment, offsets)| Some((segment.parse().ok()?, offsets)))
+      .collect();
+    Some(HermesMetadata {
+      function_maps,
+      function_offsets,
+    })
+  }
+
+  /// 原始位置 (source 下标, 0-based 行, 列) 所在函数的名称
+  pub fn function_name(&self, src_id: u32, line: u32, column: u32) -> Option<&str> {
+    let map = self.function_maps.get(src_id as usize)?.as_ref()?;
+    let key = (line + 1, column);
+    let idx = map.scopes.partition_point(|&(l, c, _)| (l, c) <= key);
+    let (_, _, name_index) = map.scopes.get(idx.checked_sub(1)?)?;
+    map.names.get(*name_index).map(|n| n.as_str())
+  }
+
+  /// 字节码 (segment, 函数 id, 函数内偏移) -> 虚拟偏移, 即 Hermes map 第 1 行的列号
+  pub fn virtual_offset(
+    &self,
+    segment_id: u32,
+    function_id: u32,
+    bytecode_offset: u32,
+  ) -> Option<u32> {
+    let base = self
+      .function_offsets
+      .get(&segment_id)?
+      .get(function_id as usize)?;
+    base.checked_add(bytecode_offset)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MAP: &str = r#"{
+    "version": 3,
+    "sources": ["App.js"],
+    "names": [],
+    "mappings": "AAAA",
+    "x_facebook_sources": [[{"names": ["<global>", "App", "onPress"], "mappings": "AAA,ECE;ICC"}]],
+    "x_hermes_function_offsets": {"0": [0, 120, 480]}
+  }"#;
+
+  #[test]
+  fn function_map_lookup() {
+    let meta = HermesMetadata::from_slice(MAP.as_bytes()).unwrap();
+    // 作用域: (1,0) <global>, (3,2) App, (4,4) onPress
+    assert_eq!(meta.function_name(0, 0, 5), Some("<global>"));
+    assert_eq!(meta.function_name(0, 2, 1), Some("<global>"));
+    assert_eq!(meta.function_name(0, 2, 2), Some("App"));
+    assert_eq!(meta.function_name(0, 3, 9), Some("onPress"));
+    assert_eq!(meta.function_name(1, 0, 0), None);
+  }
+
+  #[test]
+  fn function_offsets_and_plain_maps() {
+    let meta = HermesMetadata::from_slice(MAP.as_bytes()).unwrap();
+    assert_eq!(meta.virtual_offset(0, 1, 7), Some(127));
+    assert_eq!(meta.virtual_offset(0, 9, 0), None);
+    let plain = br#"{"version":3,"sources":[],"names":[],"mappings":""}"#;
+    assert!(HermesMetadata::from_slice(plain).is_none());
+  }
+}
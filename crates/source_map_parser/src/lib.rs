@@ -1,42 +1,404 @@
+/// 代码帧渲染 (行号栏 + 高亮下划线)
+pub mod code_frame;
 /// 通用位置 -> 上下文代码片段
 pub mod context_lookup;
+/// 上下文窗口截取与行号约定 (各上下文 API 共用)
+pub mod context_window;
+/// 推断帧所在的原始函数名
+pub mod function_name;
+/// 编译后代码摘录 (压缩代码按语句美化)
+pub mod generated_code;
+/// React Native / Hermes source map 扩展 (Metro 函数映射, 字节码函数偏移)
+pub mod hermes;
+/// token 的有序索引 (相邻映射查询)
+mod mapping_index;
+/// 行列位置约定 (列号基准, UTF-16 列)
+pub mod position;
+/// 将映射结果还原为堆栈文本
+pub mod render;
+/// 原始位置 -> 编译后位置反查
+pub mod reverse_lookup;
+/// 为缺失 sourcesContent 的 source 提供源码 (本地源码目录, git 仓库中的指定提交)
+pub mod source_provider;
+/// 合成多级 source map 链
+pub mod sourcemap_composer;
 /// 解包 source map 内容
 pub mod sourcemap_unpacker;
 /// 解析堆栈信息内容, 转换为 [`stack_transform::ErrorStack`] 结构体
 pub mod stack_transform;
 /// 生成 source map token
 pub mod token_generator;
+/// 读取 wasm 模块中的 source map 信息
+pub mod wasm;
 
-use sourcemap::SourceMap;
+use sourcemap::{DecodedMap, SourceMap};
 use std::collections::HashMap;
 
-use context_lookup::{lookup_context_from_sourcemap, ContextSnippet};
+use code_frame::CodeFrameOptions;
+use context_lookup::{context_snippet, generated_snippet, ContextSnippet};
+use context_window::{ContextOptions, HighlightSpan, LineNumbering};
+use function_name::{scan_enclosing_function_name, FunctionNameSource, OriginalFunctionName};
+use generated_code::{generated_excerpt, GeneratedExcerpt};
+use hermes::HermesMetadata;
+use mapping_index::MappingIndex;
+use position::ColumnBase;
+use render::{render_frame, TraceStyle};
+use reverse_lookup::{lookup_generated_positions, GeneratedPosition};
+use source_provider::SourceProvider;
+use stack_transform::{ErrorStack, FrameFailure, FrameInfo, Stack, StackEngine};
 use token_generator::{
-  generate_context_token_from_map, generate_source_map_token_from_map, SourceMapToken, Token,
+  column_distance, context_token, source_map_token, SourceCode, SourceMapToken, Token,
 };
 
 /// 核心门面: 绑定一个 SourceMap 提供高层 API
 pub struct SourceMapParserClient {
   sourcemap: SourceMap,
+  /// Hermes / Metro 扩展字段, 仅 React Native map 存在
+  hermes: Option<HermesMetadata>,
+  /// token 的有序索引, 供 LUB / 就近查询与高亮范围
+  index: MappingIndex,
+  options: LookupOptions,
+  /// 编译后代码 (source map 对应的 JS 文件内容), 用于摘录查询位置附近的代码
+  generated_source: Option<String>,
+  /// 编译后代码的行数, 仅附加编译后代码时已知 (映射可能在文件结束前就已终止)
+  generated_line_count: Option<u32>,
+}
+
+/// 查询列没有精确映射时选取 token 的方向
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LookupBias {
+  /// 取该位置之前最近的 token (可能落到前面的行), 与 sourcemap crate 默认行为一致
+  #[default]
+  GreatestLowerBound,
+  /// 取同一行中该列之后最近的 token
+  LeastUpperBound,
+  /// 取同一行中列距最近的 token (等距时取前者), 该行无映射时按 GreatestLowerBound 处理
+  Nearest,
+}
+
+/// 单点查询选项, 作用于所有 lookup / map 接口
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LookupOptions {
+  pub bias: LookupBias,
+  /// 输入的编译后列号与输出的原始列号所用的基准, 默认 0-based (与 source map 一致);
+  /// 直接传入浏览器堆栈中的列号时应设为 [`ColumnBase::One`]
+  pub column_base: ColumnBase,
+  /// `map_*` 接口解析出的堆栈帧列号的基准, 默认 1-based (V8 / Firefox / Safari);
+  /// wasm 与 Hermes 字节码帧的偏移不受影响
+  pub frame_column_base: ColumnBase,
+  /// 上下文接口的行号约定, 默认全部 1-based
+  pub line_numbering: LineNumbering,
+  /// 命中 token 与查询列的最大列距, 超出或 token 不在查询行时视为查询失败
+  pub max_column_distance: Option<u32>,
+}
+
+impl Default for LookupOptions {
+  fn default() -> Self {
+    LookupOptions {
+      bias: LookupBias::default(),
+      column_base: ColumnBase::Zero,
+      frame_column_base: ColumnBase::One,
+      line_numbering: LineNumbering::default(),
+      max_column_distance: None,
+    }
+  }
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum ClientError {
   #[error("invalid sourcemap: {0}")]
   InvalidSourceMap(String),
+  #[error("failed to encode sourcemap: {0}")]
+  EncodeSourceMap(String),
+}
+
+/// 单点查询失败的具体原因
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LookupError {
+  /// 行号为 1-based, 不接受 0
+  #[error("line must be 1-based, got 0")]
+  LineZero,
+  /// 行号超出编译后文件, 仅在附加了编译后代码时判断
+  #[error("line {line} is beyond the generated file ({line_count} lines)")]
+  LineOutOfRange { line: u32, line_count: u32 },
+  /// 该位置及之前没有任何映射
+  #[error("no source token covers {line}:{column}")]
+  NoMatchingToken { line: u32, column: u32 },
+  /// token 指向的 source 下标不存在
+  #[error("invalid source index {src_id}")]
+  InvalidSourceIndex { src_id: u32 },
+  /// token 只有编译后位置, 没有关联原始 source
+  #[error("token has no original source")]
+  NoSource,
+  /// source 缺少 sourcesContent, 无法截取上下文
+  #[error("no sourcesContent for {src}")]
+  MissingSourceContent { src: String },
+  /// 最近的 token 超出允许的最大列距
+  #[error("no source token within {max} columns of {line}:{column}")]
+  TokenTooFar { line: u32, column: u32, max: u32 },
 }
 
 impl SourceMapParserClient {
-  /// 通过 source map 原始字节创建客户端
+  /// 通过 source map 原始字节创建客户端 (支持普通 map, 带 `sections` 的索引 map 与 Hermes / Metro map)
   pub fn new(sourcemap_content: &[u8]) -> Result<Self, ClientError> {
-    let sm = SourceMap::from_slice(sourcemap_content)
-      .map_err(|e| ClientError::InvalidSourceMap(e.to_string()))?;
-    Ok(Self { sourcemap: sm })
+    let sm = decode_sourcemap(sourcemap_content)?;
+    Ok(Self::from_map(
+      sm,
+      HermesMetadata::from_slice(sourcemap_content),
+    ))
+  }
+
+  /// 通过多级 source map 链创建客户端 (按构建顺序排列, 如 `[ts, babel, terser]`)
+  ///
+  /// 查询结果直接落到第一级 map 的原始源码, sourcesContent 同样取自第一级
+  pub fn new_composed(sourcemap_contents: &[&[u8]]) -> Result<Self, ClientError> {
+    let maps = sourcemap_contents
+      .iter()
+      .map(|c| decode_sourcemap(c))
+      .collect::<Result<Vec<_>, _>>()?;
+    let sm = crate::sourcemap_composer::compose_sourcemaps(&maps)
+      .ok_or_else(|| ClientError::InvalidSourceMap("empty sourcemap chain".into()))?;
+    // 合成后 source 下标已重排, 不再保留 Hermes 函数映射
+    Ok(Self::from_map(sm, None))
+  }
+
+  fn from_map(sourcemap: SourceMap, hermes: Option<HermesMetadata>) -> Self {
+    Self {
+      index: MappingIndex::new(&sourcemap),
+      sourcemap,
+      hermes,
+      options: LookupOptions::default(),
+      generated_source: None,
+      generated_line_count: None,
+    }
+  }
+
+  /// 附加编译后代码 (map 对应的 JS 文件内容); 之后 `map_error_stack` 的映射帧会附带编译后代码摘录
+  pub fn with_generated_source(mut self, source: impl Into<String>) -> Self {
+    let source = source.into();
+    self.generated_line_count = Some(source.lines().count() as u32);
+    self.generated_source = Some(source);
+    self
+  }
+
+  /// source map 的 `file` 字段, 即编译后文件名 (通常相对 map 所在位置)
+  pub fn generated_file(&self) -> Option<&str> {
+    self.sourcemap.get_file()
+  }
+
+  /// 用 `provider` 补全缺失的 sourcesContent (已内嵌的源码不变), 之后上下文查询、`unpack_all_sources`
+  /// 与 `to_sourcemap_json` 均使用补全后的源码; provider 无法提供的 source 保持缺失
+  ///
+  /// 调用时即读取全部缺失的 source, 只需映射位置时不必附加
+  pub fn with_source_provider(mut self, provider: &dyn SourceProvider) -> Self {
+    for i in 0..self.sourcemap.get_source_count() {
+      if self.sourcemap.get_source_contents(i).is_some() {
+        continue;
+      }
+      let loaded = self
+        .sourcemap
+        .get_source(i)
+        .and_then(|src| provider.load_source(src));
+      if let Some(content) = loaded {
+        self.sourcemap.set_source_contents(i, Some(&content));
+      }
+    }
+    self
+  }
+
+  /// 是否有 source 缺少 sourcesContent (此时上下文接口需附加编译后代码才能回退)
+  pub fn missing_sources_content(&self) -> bool {
+    (0..self.sourcemap.get_source_count()).any(|i| self.sourcemap.get_source_contents(i).is_none())
+  }
+
+  /// 摘录编译后代码在查询位置附近的片段, 目标行过长 (压缩代码) 时按语句美化;
+  /// 未附加编译后代码或行号超出时为 None
+  pub fn lookup_generated_excerpt(
+    &self,
+    line: u32,
+    column: u32,
+    context: impl Into<ContextOptions>,
+  ) -> Option<GeneratedExcerpt> {
+    let source = self.generated_source.as_deref()?;
+    let line0 = line.checked_sub(1)?;
+    let column0 = self.options.column_base.to_zero_based(column);
+    // 高亮到同一行的下一个映射
+    let span_end = self.index.next_generated_column(line0, column0);
+    let mut excerpt = generated_excerpt(source, line0, column0, span_end, context.into())?;
+    excerpt.column = column;
+    Some(excerpt)
+  }
+
+  /// 设置查询选项 (bias / 最大列距)
+  pub fn with_lookup_options(mut self, options: LookupOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  /// 将当前绑定的 (展平/合成后的) source map 序列化为 JSON 字节
+  pub fn to_sourcemap_json(&self) -> Result<Vec<u8>, ClientError> {
+    let mut buf = Vec::new();
+    self
+      .sourcemap
+      .to_writer(&mut buf)
+      .map_err(|e| ClientError::EncodeSourceMap(e.to_string()))?;
+    Ok(buf)
   }
 
   /// 查找原始 token (1-based 行)
   pub fn lookup_token(&self, line: u32, column: u32) -> Option<SourceMapToken> {
-    generate_source_map_token_from_map(&self.sourcemap, line, column)
+    self.try_lookup_token(line, column).ok()
+  }
+
+  /// 同 [`Self::lookup_token`], 失败时返回具体原因
+  pub fn try_lookup_token(&self, line: u32, column: u32) -> Result<SourceMapToken, LookupError> {
+    let token = self.resolve_token(line, column)?;
+    let base = self.options.column_base;
+    let mut tok = source_map_token(&token, line, base.to_zero_based(column));
+    tok.column = base.from_zero_based(tok.column);
+    Ok(tok)
+  }
+
+  /// 编译后位置 (1-based 行, 按 column_base 的列) -> sourcemap token, 校验行号与 source 下标
+  fn resolve_token(
+    &self,
+    line: u32,
+    input_column: u32,
+  ) -> Result<sourcemap::Token<'_>, LookupError> {
+    let column = self.options.column_base.to_zero_based(input_column);
+    if line == 0 {
+      return Err(LookupError::LineZero);
+    }
+    if let Some(line_count) = self.generated_line_count.filter(|&n| line > n) {
+      return Err(LookupError::LineOutOfRange { line, line_count });
+    }
+    let token = match self.options.bias {
+      LookupBias::GreatestLowerBound => self.sourcemap.lookup_token(line - 1, column),
+      LookupBias::LeastUpperBound => self.token_on_line_after(line - 1, column),
+      LookupBias::Nearest => {
+        let before = self.token_on_line_before(line - 1, column);
+        let after = self.token_on_line_after(line - 1, column);
+        match (before, after) {
+          (Some(b), Some(a)) => {
+            let closer = a.get_dst_col() - column < column - b.get_dst_col();
+            Some(if closer { a } else { b })
+          }
+          (Some(t), None) | (None, Some(t)) => Some(t),
+          (None, None) => self.sourcemap.lookup_token(line - 1, column),
+        }
+      }
+    }
+    .ok_or(LookupError::NoMatchingToken {
+      line,
+      column: input_column,
+    })?;
+    if let Some(max) = self.options.max_column_distance {
+      if column_distance(&token, line, column).is_none_or(|d| d > max) {
+        return Err(LookupError::TokenTooFar {
+          line,
+          column: input_column,
+          max,
+        });
+      }
+    }
+    let src_id = token.get_src_id();
+    if src_id != !0 && token.get_source().is_none() {
+      return Err(LookupError::InvalidSourceIndex { src_id });
+    }
+    Ok(token)
+  }
+
+  /// 同一编译后行 (0-based) 中列 <= column 的最后一个 token
+  fn token_on_line_before(&self, line: u32, column: u32) -> Option<sourcemap::Token<'_>> {
+    let idx = self
+      .index
+      .generated
+      .partition_point(|&(l, c, _)| (l, c) <= (line, column));
+    let &(l, _, i) = self.index.generated.get(idx.checked_sub(1)?)?;
+    (l == line).then(|| self.sourcemap.get_token(i)).flatten()
+  }
+
+  /// 同一编译后行 (0-based) 中列 >= column 的第一个 token
+  fn token_on_line_after(&self, line: u32, column: u32) -> Option<sourcemap::Token<'_>> {
+    let idx = self
+      .index
+      .generated
+      .partition_point(|&(l, c, _)| (l, c) < (line, column));
+    let &(l, _, i) = self.index.generated.get(idx)?;
+    (l == line).then(|| self.sourcemap.get_token(i)).flatten()
+  }
+
+  /// 截取上下文前的检查: token 需关联 source 且带 sourcesContent
+  fn resolve_token_with_source(
+    &self,
+    line: u32,
+    column: u32,
+  ) -> Result<sourcemap::Token<'_>, LookupError> {
+    let token = self.resolve_token(line, column)?;
+    let src = token.get_source().ok_or(LookupError::NoSource)?;
+    if token.get_source_view().is_none() {
+      return Err(LookupError::MissingSourceContent {
+        src: src.to_string(),
+      });
+    }
+    Ok(token)
+  }
+
+  /// 同 [`Self::resolve_token_with_source`], 但缺少 sourcesContent 且附加了编译后代码时
+  /// 返回 token 与编译后代码摘录作为回退上下文
+  fn resolve_token_or_generated(
+    &self,
+    line: u32,
+    column: u32,
+    options: ContextOptions,
+  ) -> Result<(sourcemap::Token<'_>, Option<GeneratedExcerpt>), LookupError> {
+    match self.resolve_token_with_source(line, column) {
+      Ok(token) => Ok((token, None)),
+      Err(e @ LookupError::MissingSourceContent { .. }) => {
+        let generated = self
+          .lookup_generated_excerpt(line, column, options)
+          .ok_or(e)?;
+        Ok((self.resolve_token(line, column)?, Some(generated)))
+      }
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Hermes 字节码位置 (segment, 函数 id, 函数内字节码偏移) -> 原始 token
+  ///
+  /// 依赖 `x_hermes_function_offsets`, 适用于 Hermes 崩溃报告中的 `FunctionID` / `ByteCodeOffset`
+  pub fn lookup_hermes_bytecode(
+    &self,
+    segment_id: u32,
+    function_id: u32,
+    bytecode_offset: u32,
+  ) -> Option<SourceMapToken> {
+    let offset = self
+      .hermes
+      .as_ref()?
+      .virtual_offset(segment_id, function_id, bytecode_offset)?;
+    self.lookup_token(1, self.options.column_base.from_zero_based(offset))
+  }
+
+  /// 反查：原始源码路径 + 行 (1-based) + 可选列 -> 所有对应的编译后位置
+  pub fn lookup_generated_positions(
+    &self,
+    source: &str,
+    line: u32,
+    column: Option<u32>,
+  ) -> Vec<GeneratedPosition> {
+    let base = self.options.column_base;
+    let mut positions = lookup_generated_positions(
+      &self.sourcemap,
+      source,
+      line,
+      column.map(|c| base.to_zero_based(c)),
+    );
+    for p in &mut positions {
+      p.column = base.from_zero_based(p.column);
+    }
+    positions
   }
 
   /// 解包所有源码
@@ -44,39 +406,149 @@ impl SourceMapParserClient {
     crate::sourcemap_unpacker::unpack_sources(&self.sourcemap)
   }
 
-  /// 带上下文源码 (context 行向前向后扩展) 获取 Token
+  /// 带上下文源码获取 Token; `context` 为前后对称的行数或 [`ContextOptions`]
   pub fn lookup_token_with_context(
     &self,
     line: u32,
     column: u32,
-    context_lines: u32,
+    context: impl Into<ContextOptions>,
   ) -> Option<Token> {
-    generate_context_token_from_map(&self.sourcemap, line, column, context_lines)
+    self
+      .try_lookup_token_with_context(line, column, context)
+      .ok()
   }
 
-  /// 通用能力：传入编译后行/列 + 上下文行数，返回原始源码上下文片段 (适用于非错误堆栈场景)
+  /// 同 [`Self::lookup_token_with_context`], 失败时返回具体原因
+  pub fn try_lookup_token_with_context(
+    &self,
+    line: u32,
+    column: u32,
+    context: impl Into<ContextOptions>,
+  ) -> Result<Token, LookupError> {
+    let options = context.into();
+    let (token, generated) = self.resolve_token_or_generated(line, column, options)?;
+    let base = self.options.column_base;
+    let mut tok = context_token(
+      &self.index,
+      &token,
+      line,
+      base.to_zero_based(column),
+      options,
+      self.options.line_numbering,
+    );
+    tok.column = base.from_zero_based(tok.column);
+    tok.generated = generated;
+    Ok(tok)
+  }
+
+  /// 通用能力：传入编译后行/列 + 上下文行数 (或 [`ContextOptions`])，返回原始源码上下文片段 (适用于非错误堆栈场景)
   pub fn lookup_context(
     &self,
     line: u32,
     column: u32,
-    context_lines: u32,
+    context: impl Into<ContextOptions>,
   ) -> Option<ContextSnippet> {
-    lookup_context_from_sourcemap(&self.sourcemap, line, column, context_lines)
+    self.try_lookup_context(line, column, context).ok()
+  }
+
+  /// 同 [`Self::lookup_context`], 失败时返回具体原因
+  pub fn try_lookup_context(
+    &self,
+    line: u32,
+    column: u32,
+    context: impl Into<ContextOptions>,
+  ) -> Result<ContextSnippet, LookupError> {
+    let options = context.into();
+    let (token, generated) = self.resolve_token_or_generated(line, column, options)?;
+    let base = self.options.column_base;
+    let column0 = base.to_zero_based(column);
+    let mut snippet = match generated {
+      Some(generated) => generated_snippet(&token, line, column0, Some(generated)),
+      None => context_snippet(
+        &self.index,
+        &token,
+        line,
+        column0,
+        options,
+        self.options.line_numbering,
+      )
+      .ok_or(LookupError::NoSource)?,
+    };
+    snippet.column = base.from_zero_based(snippet.column);
+    Ok(snippet)
+  }
+
+  /// 推断编译后位置所在的原始函数名 (1-based 行)
+  ///
+  /// Hermes / Metro map 优先取 `x_facebook_sources` 函数映射; 其次从 sourcesContent 向前扫描
+  /// 最近的具名函数; 仍找不到时, 若提供了调用方帧的编译后位置 `caller`, 取调用点 token 的原始名
+  /// (即被调函数名)
+  pub fn lookup_function_name(
+    &self,
+    line: u32,
+    column: u32,
+    caller: Option<(u32, u32)>,
+  ) -> Option<OriginalFunctionName> {
+    self.function_name_at(self.resolve_token(line, column).ok(), caller)
+  }
+
+  /// 同 [`Self::lookup_function_name`], `token` 为已解析的编译后位置
+  fn function_name_at(
+    &self,
+    token: Option<sourcemap::Token<'_>>,
+    caller: Option<(u32, u32)>,
+  ) -> Option<OriginalFunctionName> {
+    let from_function_map = token.and_then(|t| {
+      let meta = self.hermes.as_ref()?;
+      meta.function_name(t.get_src_id(), t.get_src_line(), t.get_src_col())
+    });
+    if let Some(name) = from_function_map {
+      return Some(OriginalFunctionName {
+        name: name.to_string(),
+        source: FunctionNameSource::FunctionMap,
+      });
+    }
+    let scanned = token.and_then(|t| {
+      let view = t.get_source_view()?;
+      scan_enclosing_function_name(view.source(), t.get_src_line(), t.get_src_col())
+    });
+    if let Some(name) = scanned {
+      return Some(OriginalFunctionName {
+        name,
+        source: FunctionNameSource::SourceScan,
+      });
+    }
+    let (caller_line, caller_column) = caller?;
+    let name = self.lookup_token(caller_line, caller_column)?.name?;
+    Some(OriginalFunctionName {
+      name,
+      source: FunctionNameSource::CallSite,
+    })
+  }
+
+  /// 帧的查询列 (按 `column_base`), 帧列号按 `frame_column_base` 解释
+  fn frame_column(&self, st: &Stack<'_>) -> u32 {
+    let column0 = st.zero_based_column(self.options.frame_column_base);
+    self.options.column_base.from_zero_based(column0)
   }
 
   /// 便捷：单行堆栈映射 (解析+还原) - 无上下文
   pub fn map_stack_line(&self, stack_line: &str) -> Option<SourceMapToken> {
     if let Some(stack) = crate::stack_transform::parse_stack_line(stack_line) {
-      self.lookup_token(stack.line, stack.column)
+      self.lookup_token(stack.line, self.frame_column(&stack))
     } else {
       None
     }
   }
 
   /// 便捷：单行堆栈映射 (带上下文) -> Token 结构 (包含多行)
-  pub fn map_stack_line_with_context(&self, stack_line: &str, context_lines: u32) -> Option<Token> {
+  pub fn map_stack_line_with_context(
+    &self,
+    stack_line: &str,
+    context: impl Into<ContextOptions>,
+  ) -> Option<Token> {
     if let Some(stack) = crate::stack_transform::parse_stack_line(stack_line) {
-      self.lookup_token_with_context(stack.line, stack.column, context_lines)
+      self.lookup_token_with_context(stack.line, self.frame_column(&stack), context)
     } else {
       None
     }
@@ -86,7 +558,7 @@ impl SourceMapParserClient {
   pub fn map_stack_trace(&self, trace: &str) -> Vec<SourceMapToken> {
     crate::stack_transform::parse_stack_trace(trace)
       .into_iter()
-      .filter_map(|s| self.lookup_token(s.line, s.column))
+      .filter_map(|s| self.lookup_token(s.line, self.frame_column(&s)))
       .collect()
   }
 
@@ -96,23 +568,92 @@ impl SourceMapParserClient {
     error_stack_raw: &str,
     context_lines: Option<u32>,
   ) -> MappedErrorStack {
-    let es = crate::stack_transform::ErrorStack::from_raw(error_stack_raw);
-    let mut frames_simple = Vec::new();
-    let mut frames_with_context = Vec::new();
-    for st in &es.stacks {
-      if let Some(cl) = context_lines {
-        if let Some(tok) = self.lookup_token_with_context(st.line, st.column, cl) {
-          frames_with_context.push(tok);
+    self.map_error_stack_inner(error_stack_raw, context_lines.map(ContextOptions::from))
+  }
+
+  /// 同 [`Self::map_error_stack`], 上下文可分别指定前后行数与最大行宽
+  pub fn map_error_stack_with_context(
+    &self,
+    error_stack_raw: &str,
+    context: impl Into<ContextOptions>,
+  ) -> MappedErrorStack {
+    self.map_error_stack_inner(error_stack_raw, Some(context.into()))
+  }
+
+  fn map_error_stack_inner(
+    &self,
+    error_stack_raw: &str,
+    context: Option<ContextOptions>,
+  ) -> MappedErrorStack {
+    let es = ErrorStack::from_raw(error_stack_raw);
+    let engine = crate::stack_transform::detect_engine(error_stack_raw);
+    MappedErrorStack::map_parsed(&es, engine, context, &|_| Some(self))
+  }
+
+  /// 映射单个帧, 无法映射时给出原因
+  fn map_frame(
+    &self,
+    st: &Stack<'_>,
+    caller: Option<(u32, u32)>,
+    context: Option<ContextOptions>,
+  ) -> FrameResult {
+    if let Some(reason) = st.unmappable_reason() {
+      return FrameResult::Failed { reason };
+    }
+    let column = self.frame_column(st);
+    // token 只解析一次, 结果、上下文与函数名共用
+    let token = match self.resolve_token(st.line, column) {
+      Ok(token) => token,
+      Err(e) => {
+        return FrameResult::Failed {
+          reason: FrameFailure::Lookup(e),
         }
-      } else if let Some(tok) = self.lookup_token(st.line, st.column) {
-        frames_simple.push(tok);
       }
-    }
-    MappedErrorStack {
-      error_message: es.error_message,
-      frames: frames_simple,
-      frames_with_context,
-    }
+    };
+    let base = self.options.column_base;
+    let column0 = base.to_zero_based(column);
+    let tok = source_map_token(&token, st.line, column0);
+    // 缺少 sourcesContent 时只有编译后代码摘录, 它同时作为上下文的回退
+    let (context_lines, span) = context
+      .filter(|_| token.get_source_view().is_some())
+      .map(|c| {
+        let t = context_token(
+          &self.index,
+          &token,
+          st.line,
+          column0,
+          c,
+          self.options.line_numbering,
+        );
+        (Some(t.source_code), t.span)
+      })
+      .unwrap_or_default();
+    let generated = self.lookup_generated_excerpt(st.line, column, context.unwrap_or_default());
+    FrameResult::Mapped(MappedLocation {
+      src: tok.src,
+      line: tok.line,
+      column: base.from_zero_based(tok.column),
+      name: tok.name,
+      column_distance: tok.column_distance,
+      function_name: self.function_name_at(Some(token), caller),
+      context: context_lines,
+      span,
+      generated,
+    })
+  }
+}
+
+/// 解析 source map 字节; 索引 map 会被展平为普通 map, 以便后续查询统一走同一套逻辑
+pub(crate) fn decode_sourcemap(sourcemap_content: &[u8]) -> Result<SourceMap, ClientError> {
+  let decoded = sourcemap::decode_slice(sourcemap_content)
+    .map_err(|e| ClientError::InvalidSourceMap(e.to_string()))?;
+  match decoded {
+    DecodedMap::Regular(sm) => Ok(sm),
+    DecodedMap::Index(smi) => smi
+      .flatten()
+      .map_err(|e| ClientError::InvalidSourceMap(e.to_string())),
+    // 扩展字段由 HermesMetadata 单独解析, 这里只取普通映射部分
+    DecodedMap::Hermes(smh) => Ok((*smh).clone()),
   }
 }
 
@@ -120,8 +661,235 @@ impl SourceMapParserClient {
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct MappedErrorStack {
   pub error_message: String,
-  pub frames: Vec<SourceMapToken>,
-  pub frames_with_context: Vec<Token>,
+  /// 每个输入帧对应一项, 顺序与输入一致
+  pub frames: Vec<MappedStackFrame>,
+  /// 输入堆栈识别出的引擎, 决定 [`MappedErrorStack::render`] 的输出格式
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub engine: Option<StackEngine>,
+  /// `[cause]:` / `Caused by:` 对应的下一级错误
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub cause: Option<Box<MappedErrorStack>>,
+  /// AggregateError 的子错误
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub errors: Vec<MappedErrorStack>,
+}
+
+/// 单个输入帧的映射结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MappedStackFrame {
+  /// 输入中的原始帧行
+  pub raw: String,
+  /// 帧的结构化属性
+  pub frame: FrameInfo,
+  #[serde(flatten)]
+  pub result: FrameResult,
+}
+
+impl MappedStackFrame {
+  /// 映射成功时的原始位置
+  pub fn mapped(&self) -> Option<&MappedLocation> {
+    match &self.result {
+      FrameResult::Mapped(loc) => Some(loc),
+      FrameResult::Failed { .. } => None,
+    }
+  }
+
+  /// 映射失败时的原因
+  pub fn failure(&self) -> Option<&FrameFailure> {
+    match &self.result {
+      FrameResult::Mapped(_) => None,
+      FrameResult::Failed { reason } => Some(reason),
+    }
+  }
+}
+
+/// 帧映射成功或失败, 序列化为 `status: "mapped" | "failed"`
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum FrameResult {
+  Mapped(MappedLocation),
+  Failed { reason: FrameFailure },
+}
+
+/// 帧映射到的原始位置
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MappedLocation {
+  pub src: Option<String>,
+  /// 原始 (1-based) 行号
+  pub line: u32,
+  pub column: u32,
+  /// 原始标识符 (source map `names` 中的条目)
+  pub name: Option<String>,
+  /// 命中 token 与帧列号的列距, token 不在帧所在行时为 None
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub column_distance: Option<u32>,
+  /// 所在原始函数名
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub function_name: Option<OriginalFunctionName>,
+  /// 上下文源码, 仅传入 context_lines 且有 sourcesContent 时存在
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub context: Option<Vec<SourceCode>>,
+  /// 目标行上映射表达式的范围, 与 `context` 同时存在
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub span: Option<HighlightSpan>,
+  /// 编译后代码摘录, 仅在 client 附加了编译后代码时存在; 缺少 sourcesContent 时作为回退上下文
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub generated: Option<GeneratedExcerpt>,
+}
+
+impl MappedLocation {
+  /// 将上下文渲染为代码帧, 无上下文时为 None
+  pub fn code_frame(&self, options: CodeFrameOptions) -> Option<String> {
+    let context = self.context.as_ref()?;
+    Some(code_frame::render_code_frame(
+      context.iter().map(|c| code_frame::CodeFrameLine {
+        line: c.line,
+        code: &c.raw,
+        is_target: c.is_stack_line,
+      }),
+      self.span,
+      options,
+    ))
+  }
+}
+
+impl MappedErrorStack {
+  /// 映射解析好的错误, cause 与 AggregateError 子错误各自独立映射; 每个帧使用 `client_for` 选出的 client,
+  /// 适用于帧分属多个 bundle (各有各的 map) 的堆栈, 选不到 client 的帧记为 [`FrameFailure::NoSourceMap`]
+  pub fn map_parsed<'c, F>(
+    es: &ErrorStack<'_>,
+    engine: Option<StackEngine>,
+    context: Option<ContextOptions>,
+    client_for: &F,
+  ) -> Self
+  where
+    F: Fn(&Stack<'_>) -> Option<&'c SourceMapParserClient>,
+  {
+    let frames = es
+      .stacks
+      .iter()
+      .enumerate()
+      .map(|(idx, st)| {
+        let result = match client_for(st) {
+          Some(client) => {
+            // 调用方帧需来自同一文件, 其调用点 token 才可能记录本帧函数名
+            let caller = es
+              .stacks
+              .get(idx + 1)
+              .filter(|c| c.source_file == st.source_file)
+              .map(|c| (c.line, client.frame_column(c)));
+            client.map_frame(st, caller, context)
+          }
+          None => FrameResult::Failed {
+            reason: st.unmappable_reason().unwrap_or(FrameFailure::NoSourceMap),
+          },
+        };
+        MappedStackFrame {
+          raw: st.original_raw.to_string(),
+          frame: st.into(),
+          result,
+        }
+      })
+      .collect();
+    MappedErrorStack {
+      error_message: es.error_message.clone(),
+      frames,
+      engine,
+      cause: es
+        .cause
+        .as_ref()
+        .map(|c| Box::new(Self::map_parsed(c, engine, context, client_for))),
+      errors: es
+        .errors
+        .iter()
+        .map(|e| Self::map_parsed(e, engine, context, client_for))
+        .collect(),
+    }
+  }
+
+  /// 深度优先列出本错误及所有 cause / 子错误的帧, 顺序同 [`ErrorStack::all_stacks`]
+  pub fn all_frames(&self) -> Vec<&MappedStackFrame> {
+    let mut out: Vec<&MappedStackFrame> = self.frames.iter().collect();
+    if let Some(cause) = &self.cause {
+      out.extend(cause.all_frames());
+    }
+    for err in &self.errors {
+      out.extend(err.all_frames());
+    }
+    out
+  }
+
+  /// 还原为堆栈文本: 帧格式与输入引擎一致, 未能映射的帧原样保留, 顺序与输入相同
+  ///
+  /// 帧名优先取推断出的原始函数名, 其次取映射 token 的原始名, 均无时保留压缩后的帧名
+  pub fn render(&self) -> String {
+    let mut lines = Vec::new();
+    self.render_into(&mut lines, "", "", None);
+    lines.join("\n")
+  }
+
+  /// 同 [`Self::render`], 并在每个带上下文的帧下方附上代码帧, 附加了编译后代码时再附上编译后代码摘录
+  pub fn render_with_code_frames(&self, options: CodeFrameOptions) -> String {
+    let mut lines = Vec::new();
+    self.render_into(&mut lines, "", "", Some(options));
+    lines.join("\n")
+  }
+
+  fn render_into(
+    &self,
+    lines: &mut Vec<String>,
+    indent: &str,
+    prefix: &str,
+    code_frames: Option<CodeFrameOptions>,
+  ) {
+    let style = TraceStyle::for_engine(self.engine);
+    let mut message = self.error_message.lines();
+    let first = message.next().unwrap_or("");
+    if !prefix.is_empty() || !first.is_empty() {
+      lines.push(format!("{indent}{prefix}{first}").trim_end().to_string());
+    }
+    lines.extend(message.map(|l| format!("{indent}{l}")));
+    for f in &self.frames {
+      let text = match f.mapped() {
+        Some(loc) => {
+          let name = loc
+            .function_name
+            .as_ref()
+            .map(|n| n.name.as_str())
+            .or(loc.name.as_deref())
+            .unwrap_or(&f.frame.name);
+          let src = loc.src.as_deref().unwrap_or("");
+          render_frame(style, name, src, loc.line, loc.column, &f.frame)
+        }
+        None => f.raw.clone(),
+      };
+      lines.push(format!("{indent}{}{text}", style.indent()));
+      let (Some(options), Some(loc)) = (code_frames, f.mapped()) else {
+        continue;
+      };
+      let pad = format!("{indent}{}  ", style.indent());
+      for l in loc.code_frame(options).iter().flat_map(|f| f.lines()) {
+        lines.push(format!("{pad}{l}"));
+      }
+      if let Some(generated) = &loc.generated {
+        lines.push(format!("{pad}generated:"));
+        for l in generated.code_frame(options).lines() {
+          lines.push(format!("{pad}{l}"));
+        }
+      }
+    }
+    if let Some(cause) = &self.cause {
+      cause.render_into(lines, indent, "Caused by: ", code_frames);
+    }
+    if !self.errors.is_empty() {
+      lines.push(format!("{indent}  [errors]: ["));
+      let nested = format!("{indent}    ");
+      for err in &self.errors {
+        err.render_into(lines, &nested, "", code_frames);
+      }
+      lines.push(format!("{indent}  ]"));
+    }
+  }
 }
 
 #[cfg(test)]
@@ -143,6 +911,58 @@ mod tests {
     assert!(token.src.unwrap().ends_with("src/a.js"));
   }
 
+  #[test]
+  fn test_client_lookup_original_name() {
+    let sm = br#"{
+			"version":3,
+			"file":"min.js",
+			"sources":["src/a.js"],
+			"sourcesContent":["function add(a,b){\n  return a+b;\n}\n"],
+			"names":["add","a","b"],
+			"mappings":"AAAA,SAASA,IAAI,CAACC,CAAC,EAAEC,CAAC,EAAE;EACrB,OAAOD,CAAC,GAAGC,CAAC;AACjB"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    assert_eq!(client.lookup_token(1, 0).unwrap().name, None);
+    assert_eq!(
+      client.lookup_token(1, 9).unwrap().name.as_deref(),
+      Some("add")
+    );
+    let tok = client.lookup_token_with_context(1, 9, 0).unwrap();
+    assert_eq!(tok.name.as_deref(), Some("add"));
+    let snippet = client.lookup_context(1, 9, 0).unwrap();
+    assert_eq!(snippet.name.as_deref(), Some("add"));
+  }
+
+  #[test]
+  fn test_client_lookup_function_name() {
+    // 生成代码第 1 行: `function h(e){e()}` 对应原始 handleClick 函数体内 `cb()` 调用
+    let sm = br#"{
+			"version":3,
+			"file":"min.js",
+			"sources":["src/a.js"],
+			"sourcesContent":["function handleClick(cb) {\n  cb();\n}\nhandleClick(run);\n"],
+			"names":["handleClick","cb"],
+			"mappings":"AAAA,SAASA,EAAYC,GACnBA;AAEFD"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    let scanned = client.lookup_function_name(1, 15, None).unwrap();
+    assert_eq!(scanned.name, "handleClick");
+    assert_eq!(scanned.source, FunctionNameSource::SourceScan);
+    // 顶层代码扫描不到函数, 回退到调用方调用点 token
+    let call_site = client.lookup_function_name(2, 0, Some((2, 0))).unwrap();
+    assert_eq!(call_site.name, "handleClick");
+    assert_eq!(call_site.source, FunctionNameSource::CallSite);
+    assert!(client.lookup_function_name(2, 0, None).is_none());
+
+    let mapped = client.map_error_stack(
+      "Error: x\n  at e (https://a/min.js:1:15)\n  at https://a/min.js:2:0",
+      None,
+    );
+    let first = mapped.frames[0].mapped().unwrap();
+    assert_eq!(
+      first.function_name.as_ref().map(|f| f.name.as_str()),
+      Some("handleClick")
+    );
+  }
+
   #[test]
   fn test_client_lookup_with_context() {
     let sm = br#"{
@@ -157,6 +977,302 @@ mod tests {
     assert!(tok.source_code.len() >= 2);
   }
 
+  #[test]
+  fn test_client_lookup_errors() {
+    // 第 1 行第 4 列起才有映射; 第 2 行只有无 source 的单字段段
+    let sm = br#"{"version":3,"sources":["src/a.js"],"names":[],"mappings":"IAAA;A"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    assert_eq!(
+      client.try_lookup_token(0, 0).unwrap_err(),
+      LookupError::LineZero
+    );
+    // 未附加编译后代码时无法得知文件行数, 最后一个映射之后的行按 GLB 处理
+    let tail = SourceMapParserClient::new(
+      br#"{"version":3,"sources":["a.js"],"names":[],"mappings":"AAAA"}"#,
+    )
+    .unwrap();
+    assert_eq!(
+      tail.lookup_token(5, 0).unwrap().src.as_deref(),
+      Some("a.js")
+    );
+    assert_eq!(
+      tail
+        .with_generated_source("a()\nb()\n")
+        .try_lookup_token(5, 0)
+        .unwrap_err(),
+      LookupError::LineOutOfRange {
+        line: 5,
+        line_count: 2
+      }
+    );
+    assert_eq!(
+      client.try_lookup_token(1, 0).unwrap_err(),
+      LookupError::NoMatchingToken { line: 1, column: 0 }
+    );
+    assert!(client.lookup_token(1, 0).is_none());
+    assert_eq!(
+      client.try_lookup_context(1, 4, 1).unwrap_err(),
+      LookupError::MissingSourceContent {
+        src: "src/a.js".into()
+      }
+    );
+    assert_eq!(
+      client.try_lookup_token_with_context(2, 0, 1).unwrap_err(),
+      LookupError::NoSource
+    );
+
+    let mapped = client
+      .with_generated_source("    f()\nx\n")
+      .map_error_stack("Error: x\n    at f (https://a/min.js:9:0)", None);
+    let reason = mapped.frames[0].failure().unwrap();
+    assert_eq!(
+      reason.to_string(),
+      "line 9 is beyond the generated file (2 lines)"
+    );
+    assert_eq!(
+      serde_json::to_value(reason).unwrap()["kind"],
+      "line_out_of_range"
+    );
+  }
+
+  #[test]
+  fn test_client_lookup_bias() {
+    // 第 1 行: 第 0 列 -> a.js 1:0, 第 10 列 -> a.js 1:1; 第 2 行无映射; 第 3 行第 0 列无 source
+    let sm = br#"{"version":3,"sources":["a.js"],"names":[],"mappings":"AAAA,UAAC;;A"}"#;
+    let glb = SourceMapParserClient::new(sm).unwrap();
+    let with = |bias, max_column_distance| {
+      SourceMapParserClient::new(sm)
+        .unwrap()
+        .with_lookup_options(LookupOptions {
+          bias,
+          max_column_distance,
+          ..Default::default()
+        })
+    };
+    let lub = with(LookupBias::LeastUpperBound, None);
+    let nearest = with(LookupBias::Nearest, None);
+
+    let t = glb.lookup_token(1, 8).unwrap();
+    assert_eq!((t.column, t.column_distance), (0, Some(8)));
+    let t = lub.lookup_token(1, 8).unwrap();
+    assert_eq!((t.column, t.column_distance), (1, Some(2)));
+    assert_eq!(nearest.lookup_token(1, 8).unwrap().column, 1);
+    assert_eq!(nearest.lookup_token(1, 3).unwrap().column, 0);
+    assert_eq!(nearest.lookup_token(1, 5).unwrap().column, 0);
+
+    // 空行: GLB 落到上一行 (无列距), LUB 失败, 就近退回 GLB
+    let t = glb.lookup_token(2, 4).unwrap();
+    assert_eq!((t.column, t.column_distance), (1, None));
+    assert_eq!(
+      lub.try_lookup_token(2, 4).unwrap_err(),
+      LookupError::NoMatchingToken { line: 2, column: 4 }
+    );
+    assert_eq!(nearest.lookup_token(2, 4).unwrap().column, 1);
+
+    let strict = with(LookupBias::Nearest, Some(1));
+    assert_eq!(
+      strict.try_lookup_token(1, 8).unwrap_err(),
+      LookupError::TokenTooFar {
+        line: 1,
+        column: 8,
+        max: 1
+      }
+    );
+    assert!(strict.lookup_token(1, 9).is_some());
+    assert!(strict.lookup_token(2, 0).is_none());
+  }
+
+  #[test]
+  fn test_client_one_based_columns() {
+    // 第 1 行第 4 列 (0-based) -> a.ts 1:2
+    let sm = br#"{"version":3,"sources":["a.ts"],"sourcesContent":["let a;\n  boom();\n"],"names":[],"mappings":"IAAE"}"#;
+    let client = SourceMapParserClient::new(sm)
+      .unwrap()
+      .with_lookup_options(LookupOptions {
+        column_base: ColumnBase::One,
+        ..Default::default()
+      });
+    let tok = client.lookup_token(1, 5).unwrap();
+    assert_eq!((tok.column, tok.column_distance), (3, Some(0)));
+    assert!(client.lookup_token(1, 4).is_none());
+    assert_eq!(client.lookup_context(1, 5, 0).unwrap().column, 3);
+    let positions = client.lookup_generated_positions("a.ts", 1, Some(3));
+    assert_eq!(positions[0].column, 5);
+
+    let mapped = client.map_error_stack("Error: x\n    at f (https://a/min.js:1:5)", None);
+    assert_eq!(mapped.render(), "Error: x\n    at f (a.ts:1:3)");
+  }
+
+  #[test]
+  fn test_client_stack_columns_are_one_based() {
+    // 编译后第 1 行第 9 / 10 列 (0-based) 各有一个映射
+    let sm = br#"{"version":3,"sources":["a.js"],"names":[],"mappings":"AAAA,SAAS,CAAC"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    // V8 堆栈第 10 列 (1-based) 即 0-based 第 9 列
+    let v8 = "at f (https://a/min.js:1:10)";
+    assert_eq!(client.map_stack_line(v8).unwrap().column, 9);
+    assert_eq!(client.map_stack_trace(v8)[0].column, 9);
+    let mapped = client.map_error_stack(&format!("Error: x\n    {v8}"), None);
+    assert_eq!(mapped.frames[0].mapped().unwrap().column, 9);
+    // Hermes 字节码偏移本身为 0-based
+    let hermes = "at f (address at index.android.bundle:1:10)";
+    assert_eq!(client.map_stack_line(hermes).unwrap().column, 10);
+    // 列号已是 0-based 的堆栈可改用 frame_column_base
+    let zero = SourceMapParserClient::new(sm)
+      .unwrap()
+      .with_lookup_options(LookupOptions {
+        frame_column_base: ColumnBase::Zero,
+        ..Default::default()
+      });
+    assert_eq!(zero.map_stack_line(v8).unwrap().column, 10);
+  }
+
+  #[test]
+  fn test_client_context_line_numbering() {
+    // 编译后第 1 行 -> a.js 第 2 行 (b())
+    let sm = br#"{"version":3,"sources":["a.js"],"sourcesContent":["a()\nb()\nc()\nd()"],"names":[],"mappings":"AACA"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    let tok = client.lookup_token_with_context(1, 0, 1).unwrap();
+    let lines: Vec<_> = tok
+      .source_code
+      .iter()
+      .map(|c| (c.line, c.is_stack_line))
+      .collect();
+    assert_eq!(tok.line, 2);
+    assert_eq!(lines, vec![(1, false), (2, true), (3, false)]);
+    let snippet = client.lookup_context(1, 0, 1).unwrap();
+    let lines: Vec<_> = snippet
+      .context
+      .iter()
+      .map(|c| (c.line, c.code.as_str()))
+      .collect();
+    assert_eq!(snippet.line, 2);
+    assert_eq!(lines, vec![(1, "a()"), (2, "b()"), (3, "c()")]);
+    let stack = token_generator::get_stack_source(std::str::from_utf8(sm).unwrap(), 1, 0, Some(1));
+    assert_eq!(stack.unwrap().source_code.len(), 3);
+
+    // 兼容模式: Token.line 与 ContextLine.line 沿用旧的 0-based
+    let legacy = SourceMapParserClient::new(sm)
+      .unwrap()
+      .with_lookup_options(LookupOptions {
+        line_numbering: LineNumbering::Legacy,
+        ..Default::default()
+      });
+    let tok = legacy.lookup_token_with_context(1, 0, 1).unwrap();
+    assert_eq!((tok.line, tok.source_code[1].line), (1, 2));
+    let snippet = legacy.lookup_context(1, 0, 1).unwrap();
+    assert_eq!((snippet.line, snippet.context[1].line), (2, 1));
+  }
+
+  #[test]
+  fn test_client_code_frame() {
+    let sm = br#"{
+			"version":3,
+			"file":"min.js",
+			"sources":["src/a.js"],
+			"sourcesContent":["function add(a,b){\n  return a+b;\n}\n"],
+			"names":["add","a","b"],
+			"mappings":"AAAA,SAASA,IAAI,CAACC,CAAC,EAAEC,CAAC,EAAE;EACrB,OAAOD,CAAC,GAAGC,CAAC;AACjB"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    // 高亮从 token 列到同一原始行的下一个映射 (a)
+    let tok = client.lookup_token_with_context(1, 9, 1).unwrap();
+    assert_eq!(tok.span, Some(HighlightSpan { start: 9, end: 13 }));
+    let expected = "> 1 | function add(a,b){\n    |          ^^^^\n  2 |   return a+b;";
+    assert_eq!(tok.code_frame(CodeFrameOptions::default()), expected);
+    let snippet = client.lookup_context(1, 9, 1).unwrap();
+    assert_eq!(snippet.code_frame(CodeFrameOptions::default()), expected);
+
+    // 堆栈列号为 1-based: 第 10 列即 0-based 第 9 列
+    let mapped = client.map_error_stack("Error: x\n    at f (https://a/min.js:1:10)", Some(0));
+    assert_eq!(
+      mapped.render_with_code_frames(CodeFrameOptions::default()),
+      "Error: x\n    at add (src/a.js:1:9)\n      > 1 | function add(a,b){\n          |          ^^^^"
+    );
+  }
+
+  #[test]
+  fn test_client_generated_fallback() {
+    // 无 sourcesContent: 编译后第 1 行第 0 / 9 / 13 列
+    let sm = br#"{"version":3,"sources":["src/a.js"],"names":[],"mappings":"AAAA,SAAS,IAAI"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    assert!(client.missing_sources_content());
+    assert!(matches!(
+      client.try_lookup_context(1, 9, 1),
+      Err(LookupError::MissingSourceContent { .. })
+    ));
+    let client = client.with_generated_source("function add(a,b){return a+b}\n");
+    let snippet = client.try_lookup_context(1, 9, 1).unwrap();
+    assert!(snippet.context.is_empty());
+    let expected = "> 1 | function add(a,b){return a+b}\n    |          ^^^^";
+    assert_eq!(snippet.code_frame(CodeFrameOptions::default()), expected);
+    let tok = client.lookup_token_with_context(1, 9, 1).unwrap();
+    assert_eq!(tok.code_frame(CodeFrameOptions::default()), expected);
+
+    let mapped = client.map_error_stack("Error: x\n    at f (https://a/min.js:1:10)", Some(1));
+    let loc = mapped.frames[0].mapped().unwrap();
+    assert!(loc.context.is_none());
+    assert_eq!(
+      mapped.render_with_code_frames(CodeFrameOptions::default()),
+      format!(
+        "Error: x\n    at f (src/a.js:1:9)\n      generated:\n      {}",
+        expected.replace('\n', "\n      ")
+      )
+    );
+  }
+
+  #[test]
+  fn test_client_source_provider() {
+    let sm = br#"{"version":3,"sources":["webpack:///./src/a.js","b.js"],"names":[],"mappings":"AAAA,SCAA"}"#;
+    let provider = |src: &str| {
+      (src == "webpack:///./src/a.js").then(|| "function add(a,b){\n  return a+b;\n}\n".to_string())
+    };
+    let client = SourceMapParserClient::new(sm)
+      .unwrap()
+      .with_source_provider(&provider);
+    // b.js 无法提供, 仍缺失
+    assert!(client.missing_sources_content());
+    assert_eq!(client.unpack_all_sources().len(), 1);
+    let snippet = client.lookup_context(1, 0, 1).unwrap();
+    assert_eq!(snippet.context[0].code, "function add(a,b){");
+    assert!(matches!(
+      client.try_lookup_context(1, 9, 0),
+      Err(LookupError::MissingSourceContent { .. })
+    ));
+  }
+
+  #[test]
+  fn test_client_context_options() {
+    // 编译后第 1 行第 0 列 -> a.js 第 3 行第 23 列 (boom), 第 3 行为 30 个字符的长行
+    let sm = br#"{"version":3,"sources":["a.js"],"sourcesContent":["l1\nl2\nvar aaaaaaaaaaaaaa = x.boom();\nl4\nl5"],"names":[],"mappings":"AAEuB,IAAI"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    let options = ContextOptions::new(2, 0);
+    let snippet = client.lookup_context(1, 0, options).unwrap();
+    let lines: Vec<_> = snippet.context.iter().map(|l| l.line).collect();
+    assert_eq!(lines, vec![1, 2, 3]);
+
+    let tok = client
+      .lookup_token_with_context(1, 0, ContextOptions::new(0, 1).with_max_line_width(10))
+      .unwrap();
+    let target = &tok.source_code[0];
+    assert_eq!(target.raw, "… = x.boom(…");
+    assert_eq!(target.column_offset, Some(18));
+    assert_eq!(tok.source_code[1].column_offset, None);
+    // 原始列不变, span 换算到截断后的文本
+    assert_eq!(tok.column, 23);
+    assert_eq!(tok.span, Some(HighlightSpan { start: 6, end: 10 }));
+    assert_eq!(
+      tok.code_frame(CodeFrameOptions::default()),
+      "> 3 | … = x.boom(…\n    |       ^^^^\n  4 | l4"
+    );
+
+    let mapped = client.map_error_stack_with_context(
+      "Error: x\n    at f (https://a/min.js:1:0)",
+      ContextOptions::new(0, 0).with_max_line_width(10),
+    );
+    let loc = mapped.frames[0].mapped().unwrap();
+    assert_eq!(loc.context.as_ref().unwrap()[0].raw, "… = x.boom(…");
+  }
+
   #[test]
   fn test_client_generic_context() {
     let sm = br#"{
@@ -186,6 +1302,198 @@ mod tests {
     let _ = client.map_stack_line(line);
   }
 
+  #[test]
+  fn test_client_indexed_sourcemap() {
+    let sm = br#"{
+			"version":3,
+			"file":"min.js",
+			"sections":[
+				{"offset":{"line":0,"column":0},"map":{
+					"version":3,
+					"sources":["src/a.js"],
+					"sourcesContent":["a()\nb()\n"],
+					"names":["a"],
+					"mappings":"AAAAA"}},
+				{"offset":{"line":1,"column":0},"map":{
+					"version":3,
+					"sources":["src/b.js"],
+					"sourcesContent":["x()\ny()\nz()\n"],
+					"names":[],
+					"mappings":";AACA"}}
+			]}"#;
+    let client = SourceMapParserClient::new(sm).expect("create client from index map");
+    let first = client.lookup_token(1, 0).expect("token in first section");
+    assert_eq!(first.src.as_deref(), Some("src/a.js"));
+    assert_eq!(first.line, 1);
+    let second = client.lookup_token(3, 0).expect("token in second section");
+    assert_eq!(second.src.as_deref(), Some("src/b.js"));
+    assert_eq!(second.line, 2);
+    let snippet = client.lookup_context(3, 0, 1).expect("context");
+    assert!(snippet
+      .context
+      .iter()
+      .any(|c| c.is_target && c.code == "y()"));
+    let tok = client
+      .lookup_token_with_context(3, 0, 1)
+      .expect("token with context");
+    assert!(tok
+      .source_code
+      .iter()
+      .any(|c| c.is_stack_line && c.raw == "y()"));
+    let sources = client.unpack_all_sources();
+    assert_eq!(sources.len(), 2);
+    assert_eq!(sources["src/b.js"], "x()\ny()\nz()\n");
+  }
+
+  #[test]
+  fn test_client_composed_chain() {
+    // ts: 生成 (0,0) -> a.ts (1,2); min: 生成 (0,4) -> ts 输出 (0,0)
+    let ts = br#"{"version":3,"sources":["src/a.ts"],"sourcesContent":["let a;\n  boom();\n"],"names":["boom"],"mappings":"AACEA"}"#;
+    let min = br#"{"version":3,"file":"min.js","sources":["a.js"],"names":[],"mappings":"IAAA"}"#;
+    let client = SourceMapParserClient::new_composed(&[ts, min]).unwrap();
+    let tok = client.lookup_token(1, 4).unwrap();
+    assert_eq!(tok.src.as_deref(), Some("src/a.ts"));
+    assert_eq!((tok.line, tok.column), (2, 2));
+    assert_eq!(tok.name.as_deref(), Some("boom"));
+    let snippet = client.lookup_context(1, 4, 0).unwrap();
+    assert_eq!(snippet.context[0].code, "  boom();");
+
+    let json = client.to_sourcemap_json().unwrap();
+    let reloaded = SourceMapParserClient::new(&json).unwrap();
+    assert_eq!(reloaded.lookup_token(1, 4).unwrap().line, 2);
+    assert!(SourceMapParserClient::new_composed(&[]).is_err());
+  }
+
+  #[test]
+  fn test_map_error_stack_accounts_for_safari_frames() {
+    let sm = br#"{"version":3,"sources":["src/a.js"],"sourcesContent":["a()\n"],"names":[],"mappings":"AAAA"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    let raw = "TypeError: boom\nforEach@[native code]\nglobal code@https://a/min.js:1:0\neval code";
+    let mapped = client.map_error_stack(raw, None);
+    assert_eq!(mapped.frames.len(), 3);
+    let reasons: Vec<_> = mapped.frames.iter().map(|f| f.failure().cloned()).collect();
+    assert_eq!(
+      reasons,
+      vec![
+        Some(FrameFailure::NativeFrame),
+        None,
+        Some(FrameFailure::EvalFrame)
+      ]
+    );
+    assert_eq!(mapped.frames[0].raw, "forEach@[native code]");
+    assert_eq!(
+      serde_json::to_value(&mapped.frames[0]).unwrap()["reason"],
+      serde_json::json!({ "kind": "native_frame" })
+    );
+    assert_eq!(mapped.frames[1].mapped().unwrap().line, 1);
+    let frame = &mapped.frames[1].frame;
+    assert_eq!(
+      (frame.source_file.as_str(), frame.line, frame.column),
+      ("https://a/min.js", 1, 0)
+    );
+  }
+
+  #[test]
+  fn test_map_error_stack_keeps_eval_origin() {
+    let sm = br#"{"version":3,"sources":["src/a.js"],"names":[],"mappings":"AAAA"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    let raw = "Error: x
+    at eval (eval at compile (https://a/min.js:1:1), <anonymous>:3:4)";
+    let mapped = client.map_error_stack(raw, None);
+    let frame = &mapped.frames[0].frame;
+    assert_eq!((frame.line, frame.column), (1, 1));
+    let origin = frame.eval_origin.as_ref().unwrap();
+    assert_eq!(origin.function_name, "compile");
+    assert_eq!((origin.eval_line, origin.eval_column), (3, 4));
+  }
+
+  #[test]
+  fn test_map_error_stack_maps_cause_separately() {
+    let sm = br#"{"version":3,"sources":["src/a.js"],"sourcesContent":["a()\nb()\n"],"names":[],"mappings":"AAAA;AACA"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    let raw = "Error: outer\n    at a (https://a/min.js:1:0)\nCaused by: Error: inner\n    at b (https://a/min.js:2:0)";
+    let mapped = client.map_error_stack(raw, None);
+    assert_eq!(mapped.frames.len(), 1);
+    assert_eq!(mapped.frames[0].mapped().unwrap().line, 1);
+    let cause = mapped.cause.unwrap();
+    assert_eq!(cause.error_message, "Error: inner");
+    assert_eq!(cause.frames.len(), 1);
+    assert_eq!(cause.frames[0].mapped().unwrap().line, 2);
+  }
+
+  #[test]
+  fn test_map_parsed_selects_client_per_frame() {
+    let a = SourceMapParserClient::new(
+      br#"{"version":3,"sources":["src/a.js"],"names":[],"mappings":"AAAA"}"#,
+    )
+    .unwrap();
+    let b = SourceMapParserClient::new(
+      br#"{"version":3,"sources":["src/b.js"],"names":[],"mappings":";AAAA"}"#,
+    )
+    .unwrap();
+    let raw = "Error: outer\n    at f (https://x/a.js:1:1)\n    at g (https://x/c.js:1:1)\nCaused by: Error: inner\n    at h (https://x/b.js:2:1)";
+    let es = ErrorStack::from_raw(raw);
+    let mapped =
+      MappedErrorStack::map_parsed(&es, None, Some(1.into()), &|st| match st.source_file {
+        "https://x/a.js" => Some(&a),
+        "https://x/b.js" => Some(&b),
+        _ => None,
+      });
+    let frames = mapped.all_frames();
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0].mapped().unwrap().src.as_deref(), Some("src/a.js"));
+    // 缺少 sourcesContent 只是没有上下文, 帧仍映射成功
+    assert!(frames[0].mapped().unwrap().context.is_none());
+    assert_eq!(frames[1].failure(), Some(&FrameFailure::NoSourceMap));
+    assert_eq!(frames[2].mapped().unwrap().src.as_deref(), Some("src/b.js"));
+    assert!(mapped
+      .render()
+      .contains("Caused by: Error: inner\n    at h (src/b.js:1:0)"));
+  }
+
+  #[test]
+  fn test_map_error_stack_keeps_firefox_first_frame() {
+    let sm = br#"{"version":3,"sources":["src/a.js"],"sourcesContent":["a()\nb()\n"],"names":[],"mappings":"AAAA;AACA"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    let mapped = client.map_error_stack("a@https://a/min.js:1:0\nb@https://a/min.js:2:0", None);
+    assert_eq!(mapped.error_message, "");
+    assert_eq!(mapped.frames.len(), 2);
+    assert_eq!(mapped.frames[0].mapped().unwrap().line, 1);
+  }
+
+  #[test]
+  fn test_hermes_map_and_frames() {
+    let sm = br#"{"version":3,"sources":["App.js"],"sourcesContent":["run();\nfunction onPress() {}\n"],"names":[],"mappings":"AAAA,KACA","x_facebook_sources":[[{"names":["<global>","onPress"],"mappings":"AAA;ACC"}]],"x_hermes_function_offsets":{"0":[0,3]}}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    let raw = "Error: boom\n    at onPress (address at index.android.bundle:1:5)\n    at anonymous (address at index.android.bundle:1:0)";
+    let mapped = client.map_error_stack(raw, None);
+    assert_eq!(mapped.frames.len(), 2);
+    let first = mapped.frames[0].mapped().unwrap();
+    assert_eq!(first.line, 2);
+    let function_name = first.function_name.as_ref().unwrap();
+    assert_eq!(function_name.name, "onPress");
+    assert_eq!(function_name.source, FunctionNameSource::FunctionMap);
+    let second = mapped.frames[1].mapped().unwrap();
+    assert_eq!(second.function_name.as_ref().unwrap().name, "<global>");
+    assert_eq!(client.lookup_hermes_bytecode(0, 1, 2).unwrap().line, 2);
+  }
+
+  #[test]
+  fn test_render_mapped_error_stack() {
+    let sm = br#"{"version":3,"sources":["src/a.ts"],"sourcesContent":["a()\nb()\n"],"names":["boom"],"mappings":"AAAAA;AACA"}"#;
+    let client = SourceMapParserClient::new(sm).unwrap();
+    let raw = "Error: x\n    at q (https://a/min.js:1:0)\n    at JSON.parse (<anonymous>)\n    at async r (https://a/min.js:2:0)";
+    assert_eq!(
+      client.map_error_stack(raw, Some(1)).render(),
+      "Error: x\n    at boom (src/a.ts:1:0)\n    at JSON.parse (<anonymous>)\n    at async r (src/a.ts:2:0)"
+    );
+    let safari = "q@https://a/min.js:1:0\nforEach@[native code]\nr@https://a/min.js:2:0";
+    assert_eq!(
+      client.map_error_stack(safari, None).render(),
+      "boom@src/a.ts:1:0\nforEach@[native code]\nr@src/a.ts:2:0"
+    );
+  }
+
   #[test]
   fn test_parse_stack_trace_multi() {
     let trace = "at foo (https://example.com/app.js:10:5)\n@https://example.com/app.js:20:15";
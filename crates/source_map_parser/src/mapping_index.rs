@@ -0,0 +1,80 @@
+use sourcemap::SourceMap;
+
+/// source map token 的有序索引, 建立一次后按二分查找定位相邻映射
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MappingIndex {
+  /// 按编译后位置排序的 (行, 列, token 下标)
+  pub generated: Vec<(u32, u32, u32)>,
+  /// 按原始位置排序的 (source 下标, 行, 列), 不含无 source 的 token
+  original: Vec<(u32, u32, u32)>,
+}
+
+impl MappingIndex {
+  pub fn new(sm: &SourceMap) -> Self {
+    let mut generated: Vec<_> = sm
+      .tokens()
+      .enumerate()
+      .map(|(i, t)| (t.get_dst_line(), t.get_dst_col(), i as u32))
+      .collect();
+    generated.sort_unstable();
+    let mut original: Vec<_> = sm
+      .tokens()
+      .filter(|t| t.get_src_id() != !0)
+      .map(|t| (t.get_src_id(), t.get_src_line(), t.get_src_col()))
+      .collect();
+    original.sort_unstable();
+    original.dedup();
+    MappingIndex {
+      generated,
+      original,
+    }
+  }
+
+  /// 同一编译后行 (0-based) 上列大于 `column` 的第一个映射的列
+  pub fn next_generated_column(&self, line: u32, column: u32) -> Option<u32> {
+    let idx = self
+      .generated
+      .partition_point(|&(l, c, _)| (l, c) <= (line, column));
+    self
+      .generated
+      .get(idx)
+      .filter(|&&(l, _, _)| l == line)
+      .map(|&(_, c, _)| c)
+  }
+
+  /// 同一 source 的同一原始行 (0-based) 上列大于 `column` 的第一个映射的列
+  pub fn next_original_column(&self, src_id: u32, line: u32, column: u32) -> Option<u32> {
+    let idx = self
+      .original
+      .partition_point(|&entry| entry <= (src_id, line, column));
+    self
+      .original
+      .get(idx)
+      .filter(|&&(s, l, _)| (s, l) == (src_id, line))
+      .map(|&(_, _, c)| c)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_next_mapping_on_same_line() {
+    // 编译后 0:0 -> a.js 0:0, 0:9 -> a.js 0:9, 0:13 -> a.js 0:4, 1:0 -> a.js 1:0
+    let sm = SourceMap::from_slice(
+      br#"{"version":3,"sources":["a.js"],"names":[],"mappings":"AAAA,SAAS,IAAL;AACJ"}"#,
+    )
+    .unwrap();
+    let index = MappingIndex::new(&sm);
+    assert_eq!(index.next_generated_column(0, 0), Some(9));
+    assert_eq!(index.next_generated_column(0, 10), Some(13));
+    assert_eq!(index.next_generated_column(0, 13), None);
+    assert_eq!(index.next_generated_column(1, 0), None);
+    // 原始位置与编译后顺序无关
+    assert_eq!(index.next_original_column(0, 0, 0), Some(4));
+    assert_eq!(index.next_original_column(0, 0, 4), Some(9));
+    assert_eq!(index.next_original_column(0, 0, 9), None);
+    assert_eq!(index.next_original_column(1, 0, 0), None);
+  }
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+
+/// 列号约定: source map 内部统一为 0-based, 浏览器堆栈 (V8 / Firefox / Safari) 为 1-based
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnBase {
+  /// 0-based, 与 source map 一致
+  #[default]
+  Zero,
+  /// 1-based, 与浏览器堆栈一致
+  One,
+}
+
+impl ColumnBase {
+  /// 本约定下的列号 -> 0-based 列号
+  pub fn to_zero_based(self, column: u32) -> u32 {
+    match self {
+      ColumnBase::Zero => column,
+      ColumnBase::One => column.saturating_sub(1),
+    }
+  }
+
+  /// 0-based 列号 -> 本约定下的列号
+  pub fn from_zero_based(self, column: u32) -> u32 {
+    match self {
+      ColumnBase::Zero => column,
+      ColumnBase::One => column + 1,
+    }
+  }
+}
+
+/// 行内 UTF-16 列 (source map 列的单位) -> 字节偏移
+///
+/// 列落在代理对中间时取该字符起点, 超出行尾时截断到行尾
+pub fn utf16_to_byte_offset(line: &str, column: u32) -> usize {
+  let mut units = 0u32;
+  for (i, ch) in line.char_indices() {
+    units += ch.len_utf16() as u32;
+    if units > column {
+      return i;
+    }
+  }
+  line.len()
+}
+
+/// 行内字节偏移 -> UTF-16 列
+pub fn byte_to_utf16_offset(line: &str, byte: usize) -> u32 {
+  line
+    .char_indices()
+    .take_while(|&(i, _)| i < byte)
+    .map(|(_, ch)| ch.len_utf16() as u32)
+    .sum()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn column_base_round_trip() {
+    assert_eq!(ColumnBase::One.to_zero_based(1), 0);
+    assert_eq!(ColumnBase::One.to_zero_based(0), 0);
+    assert_eq!(ColumnBase::One.from_zero_based(0), 1);
+    assert_eq!(ColumnBase::Zero.to_zero_based(7), 7);
+  }
+
+  #[test]
+  fn utf16_columns_with_astral_and_multibyte_chars() {
+    // `😀` 占 2 个 UTF-16 单元 / 4 字节, `é` 占 1 个单元 / 2 字节
+    let line = "a😀é=1";
+    assert_eq!(utf16_to_byte_offset(line, 0), 0);
+    assert_eq!(utf16_to_byte_offset(line, 1), 1);
+    assert_eq!(utf16_to_byte_offset(line, 2), 1);
+    assert_eq!(utf16_to_byte_offset(line, 3), 5);
+    assert_eq!(utf16_to_byte_offset(line, 4), 7);
+    assert_eq!(utf16_to_byte_offset(line, 99), line.len());
+    assert_eq!(byte_to_utf16_offset(line, 7), 4);
+    assert_eq!(&line[utf16_to_byte_offset(line, 4)..], "=1");
+  }
+}
@@ -0,0 +1,90 @@
+use crate::stack_transform::{FrameInfo, StackEngine};
+
+/// 还原后堆栈文本的帧格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceStyle {
+  /// `    at fn (src/a.ts:12:3)`
+  V8,
+  /// `fn@src/a.ts:12:3`
+  Firefox,
+}
+
+impl TraceStyle {
+  /// 按输入引擎选择输出格式: Firefox / Safari 用 `@` 格式, 其余 (含无法识别) 用 V8 格式
+  pub fn for_engine(engine: Option<StackEngine>) -> Self {
+    match engine {
+      Some(StackEngine::Firefox | StackEngine::Safari) => TraceStyle::Firefox,
+      _ => TraceStyle::V8,
+    }
+  }
+
+  /// 帧行前缀缩进
+  pub fn indent(self) -> &'static str {
+    match self {
+      TraceStyle::V8 => "    ",
+      TraceStyle::Firefox => "",
+    }
+  }
+}
+
+/// 以指定格式输出一个已还原的帧 (不含缩进), 保留 async / new 标记
+pub fn render_frame(
+  style: TraceStyle,
+  name: &str,
+  source: &str,
+  line: u32,
+  column: u32,
+  frame: &FrameInfo,
+) -> String {
+  let location = format!("{source}:{line}:{column}");
+  match style {
+    TraceStyle::V8 => {
+      let mut out = String::from("at ");
+      if frame.is_async {
+        out.push_str("async ");
+      }
+      if name.is_empty() {
+        out.push_str(&location);
+      } else {
+        if frame.is_constructor {
+          out.push_str("new ");
+        }
+        out.push_str(&format!("{name} ({location})"));
+      }
+      out
+    }
+    TraceStyle::Firefox => {
+      let prefix = if frame.is_async { "async*" } else { "" };
+      format!("{prefix}{name}@{location}")
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn render_v8_and_firefox_frames() {
+    let frame = FrameInfo {
+      is_async: true,
+      ..Default::default()
+    };
+    assert_eq!(
+      render_frame(TraceStyle::V8, "load", "src/a.ts", 12, 3, &frame),
+      "at async load (src/a.ts:12:3)"
+    );
+    assert_eq!(
+      render_frame(TraceStyle::V8, "", "src/a.ts", 1, 0, &FrameInfo::default()),
+      "at src/a.ts:1:0"
+    );
+    assert_eq!(
+      render_frame(TraceStyle::Firefox, "load", "src/a.ts", 12, 3, &frame),
+      "async*load@src/a.ts:12:3"
+    );
+    assert_eq!(
+      TraceStyle::for_engine(Some(StackEngine::Safari)),
+      TraceStyle::Firefox
+    );
+  }
+}
@@ -0,0 +1,125 @@
+use serde::Serialize;
+use sourcemap::SourceMap;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GeneratedPosition {
+  /// 编译后 (1-based) 行号
+  pub line: u32,
+  /// 编译后列号
+  pub column: u32,
+}
+
+/// 按路径定位 source 索引: 优先全等, 其次按路径段后缀匹配 (如 `src/a.ts` 命中 `webpack:///./src/a.ts`)
+fn find_source_id(sm: &SourceMap, source: &str) -> Option<u32> {
+  let wanted = source.trim_start_matches("./");
+  let mut suffix_match = None;
+  for (idx, src) in sm.sources().enumerate() {
+    if src == source {
+      return Some(idx as u32);
+    }
+    if suffix_match.is_none()
+      && src.ends_with(wanted)
+      && src[..src.len() - wanted.len()]
+        .chars()
+        .last()
+        .is_none_or(|c| c == '/')
+    {
+      suffix_match = Some(idx as u32);
+    }
+  }
+  suffix_match
+}
+
+/// 原始位置 -> 所有对应的编译后位置 (1-based 行)
+///
+/// 指定 `column` 时返回该列的映射, 该列没有映射则取同一行内之后最近的一列;
+/// 未指定时返回该行全部映射。结果按编译后位置排序
+pub fn lookup_generated_positions(
+  sm: &SourceMap,
+  source: &str,
+  line: u32,
+  column: Option<u32>,
+) -> Vec<GeneratedPosition> {
+  if line == 0 {
+    return Vec::new();
+  }
+  let Some(src_id) = find_source_id(sm, source) else {
+    return Vec::new();
+  };
+  let on_line: Vec<_> = sm
+    .tokens()
+    .filter(|t| t.get_src_id() == src_id && t.get_src_line() == line - 1)
+    .collect();
+  let target_col = match column {
+    Some(col) => match on_line
+      .iter()
+      .map(|t| t.get_src_col())
+      .filter(|c| *c >= col)
+      .min()
+    {
+      Some(c) => Some(c),
+      None => return Vec::new(),
+    },
+    None => None,
+  };
+  let mut positions: Vec<GeneratedPosition> = on_line
+    .iter()
+    .filter(|t| target_col.is_none_or(|c| t.get_src_col() == c))
+    .map(|t| GeneratedPosition {
+      line: t.get_dst_line() + 1,
+      column: t.get_dst_col(),
+    })
+    .collect();
+  positions.sort_by_key(|p| (p.line, p.column));
+  positions.dedup();
+  positions
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use sourcemap::SourceMapBuilder;
+
+  fn sm() -> SourceMap {
+    let mut b = SourceMapBuilder::new(None);
+    // a.ts 第 2 行 (0-based 1) 第 4 列被内联到两处
+    b.add(0, 10, 1, 4, Some("webpack:///./src/a.ts"), None);
+    b.add(3, 2, 1, 4, Some("webpack:///./src/a.ts"), None);
+    b.add(0, 20, 1, 9, Some("webpack:///./src/a.ts"), None);
+    b.add(1, 0, 0, 0, Some("webpack:///./src/b.ts"), None);
+    b.into_sourcemap()
+  }
+
+  #[test]
+  fn reverse_exact_column_and_suffix_path() {
+    let positions = lookup_generated_positions(&sm(), "src/a.ts", 2, Some(4));
+    assert_eq!(
+      positions,
+      vec![
+        GeneratedPosition {
+          line: 1,
+          column: 10
+        },
+        GeneratedPosition { line: 4, column: 2 },
+      ]
+    );
+  }
+
+  #[test]
+  fn reverse_next_column_and_whole_line() {
+    let next = lookup_generated_positions(&sm(), "src/a.ts", 2, Some(5));
+    assert_eq!(
+      next,
+      vec![GeneratedPosition {
+        line: 1,
+        column: 20
+      }]
+    );
+    assert_eq!(
+      lookup_generated_positions(&sm(), "src/a.ts", 2, None).len(),
+      3
+    );
+    assert!(lookup_generated_positions(&sm(), "a.ts", 9, None).is_empty());
+    assert!(lookup_generated_positions(&sm(), "c.ts", 1, None).is_empty());
+  }
+}
@@ -0,0 +1,148 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+// 依赖 git 子进程, wasm 下不可用
+#[cfg(not(target_arch = "wasm32"))]
+mod git;
+#[cfg(not(target_arch = "wasm32"))]
+pub use git::GitSourceRoot;
+
+/// 按 source map 的 `sources[i]` 提供原始源码, 用于补全被裁掉的 sourcesContent
+pub trait SourceProvider {
+  /// `source` 为 `sources[i]` (已拼接 sourceRoot), 无法提供时返回 None
+  fn load_source(&self, source: &str) -> Option<String>;
+}
+
+impl<F: Fn(&str) -> Option<String>> SourceProvider for F {
+  fn load_source(&self, source: &str) -> Option<String> {
+    self(source)
+  }
+}
+
+/// 从本地目录 (通常为构建时的源码检出) 读取源码
+///
+/// 解析顺序: 按添加顺序取第一个匹配的前缀改写; 去掉 `webpack://` 等协议头与 `?query`;
+/// 其余部分作为相对 `root` (设置了 [`with_base_dir`](Self::with_base_dir) 时为 `root/base`) 的路径,
+/// 开头的 `/` 同样视为相对; 越出 `root` 的 `..` 视为无法提供
+#[derive(Clone, Debug)]
+pub struct LocalSourceRoot {
+  root: PathBuf,
+  base: Vec<OsString>,
+  rewrites: Vec<(String, String)>,
+}
+
+impl LocalSourceRoot {
+  pub fn new(root: impl Into<PathBuf>) -> Self {
+    LocalSourceRoot {
+      root: root.into(),
+      base: Vec::new(),
+      rewrites: Vec::new(),
+    }
+  }
+
+  /// source 相对 `root` 下的 `base` 目录解析 (通常为 map 所在目录), 其中的 `..` 最多回到 `root`,
+  /// 例如 `base` 为 `dist/assets` 时 `../../src/a.js` -> `root/src/a.js`
+  pub fn with_base_dir(mut self, base: impl AsRef<Path>) -> Self {
+    self.base = normal_components(Vec::new(), base.as_ref()).unwrap_or_default();
+    self
+  }
+
+  /// 以 `from` 开头的 source 先替换为 `to` 再解析, 例如 `webpack:///./src` -> `./src`
+  pub fn with_prefix_rewrite(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+    self.rewrites.push((from.into(), to.into()));
+    self
+  }
+
+  /// source 对应的本地路径, 改写后为空或越出 `root` 时为 None
+  pub fn resolve(&self, source: &str) -> Option<PathBuf> {
+    let path = source_path(&self.rewrites, source);
+    let parts = normal_components(self.base.clone(), Path::new(&path))?;
+    if parts == self.base {
+      return None;
+    }
+    let mut resolved = self.root.clone();
+    resolved.extend(parts);
+    Some(resolved)
+  }
+}
+
+/// 将 `path` 的各段依次追加到 `parts`, `..` 弹出上一段, 弹空时为 None
+fn normal_components(mut parts: Vec<OsString>, path: &Path) -> Option<Vec<OsString>> {
+  for c in path.components() {
+    match c {
+      Component::Normal(part) => parts.push(part.to_os_string()),
+      Component::ParentDir => {
+        parts.pop()?;
+      }
+      _ => {}
+    }
+  }
+  Some(parts)
+}
+
+impl SourceProvider for LocalSourceRoot {
+  fn load_source(&self, source: &str) -> Option<String> {
+    fs::read_to_string(self.resolve(source)?).ok()
+  }
+}
+
+/// 前缀改写 (取第一个匹配项), 去掉协议头、`?query` / `#hash` 与开头的 `/`
+fn source_path(rewrites: &[(String, String)], source: &str) -> String {
+  let rewritten = rewrites
+    .iter()
+    .find_map(|(from, to)| Some(format!("{to}{}", source.strip_prefix(from.as_str())?)));
+  let mut path = rewritten.as_deref().unwrap_or(source);
+  if let Some((_, rest)) = path.split_once("://") {
+    path = rest;
+  }
+  path = path.split(['?', '#']).next().unwrap_or(path);
+  path.trim_start_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn resolves_rewritten_and_scheme_prefixed_sources() {
+    let root = LocalSourceRoot::new("/repo").with_prefix_rewrite("webpack:///./src", "./src");
+    let resolve = |s: &str| root.resolve(s).map(|p| p.to_string_lossy().into_owned());
+    assert_eq!(
+      resolve("webpack:///./src/app.ts"),
+      Some("/repo/src/app.ts".into())
+    );
+    // 未命中改写时仍去掉协议头与 query
+    assert_eq!(
+      resolve("webpack:///lib/util.js?a1b2"),
+      Some("/repo/lib/util.js".into())
+    );
+    assert_eq!(
+      resolve("dist/../node_modules/x/index.js"),
+      Some("/repo/node_modules/x/index.js".into())
+    );
+    assert_eq!(resolve("/abs/a.js"), Some("/repo/abs/a.js".into()));
+    assert_eq!(resolve("webpack:///"), None);
+  }
+
+  #[test]
+  fn rejects_sources_escaping_the_root() {
+    let root = LocalSourceRoot::new("/repo").with_prefix_rewrite("webpack:///./src", "./src");
+    assert_eq!(root.resolve("../node_modules/x/index.js"), None);
+    assert_eq!(root.resolve("../../../../etc/passwd"), None);
+    assert_eq!(root.resolve("webpack:///./src/../../../etc/passwd"), None);
+    assert_eq!(root.resolve("src/a/../../.."), None);
+    assert_eq!(root.load_source("../../../../etc/passwd"), None);
+    // base 内的 `..` 可以回到 root, 但不能再往上
+    let root = LocalSourceRoot::new("/repo").with_base_dir("dist/assets");
+    assert_eq!(
+      root.resolve("../../node_modules/x/index.js"),
+      Some(PathBuf::from("/repo/node_modules/x/index.js"))
+    );
+    assert_eq!(
+      root.resolve("chunk.js"),
+      Some(PathBuf::from("/repo/dist/assets/chunk.js"))
+    );
+    assert_eq!(root.resolve("../../../etc/passwd"), None);
+  }
+}
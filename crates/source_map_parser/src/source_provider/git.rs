@@ -0,0 +1,224 @@
+use std::cell::RefCell;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use super::{source_path, SourceProvider};
+
+/// 从本地 git 仓库的指定提交读取源码, 不检出、不读工作区
+///
+/// 路径解析同 [`LocalSourceRoot`](super::LocalSourceRoot), 结果为相对仓库根的路径; 越出仓库根的 `..` 视为无法提供。
+/// 通过一个常驻的 `git cat-file --batch` 进程按需读取, 需要 PATH 中有 git
+pub struct GitSourceRoot {
+  repo: PathBuf,
+  commit: String,
+  rewrites: Vec<(String, String)>,
+  cat_file: RefCell<Option<CatFile>>,
+}
+
+impl GitSourceRoot {
+  /// 打开 `repo` 并将 `rev` (分支、tag、提交等) 解析为提交; 仓库或 rev 无效时报错
+  pub fn open(repo: impl Into<PathBuf>, rev: &str) -> io::Result<Self> {
+    let repo = repo.into();
+    let output = Command::new("git")
+      .arg("-C")
+      .arg(&repo)
+      .args(["rev-parse", "--verify", "--quiet", "--end-of-options"])
+      .arg(format!("{rev}^{{commit}}"))
+      .stderr(Stdio::null())
+      .output()?;
+    if !output.status.success() {
+      return Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{rev} is not a commit in git repository {}", repo.display()),
+      ));
+    }
+    Ok(GitSourceRoot {
+      repo,
+      commit: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+      rewrites: Vec::new(),
+      cat_file: RefCell::new(None),
+    })
+  }
+
+  /// 同 [`LocalSourceRoot::with_prefix_rewrite`](super::LocalSourceRoot::with_prefix_rewrite)
+  pub fn with_prefix_rewrite(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+    self.rewrites.push((from.into(), to.into()));
+    self
+  }
+
+  /// `rev` 解析得到的完整提交哈希
+  pub fn commit(&self) -> &str {
+    &self.commit
+  }
+
+  /// source 对应的仓库内路径 (`/` 分隔), 为空或越出仓库根时为 None
+  pub fn resolve(&self, source: &str) -> Option<String> {
+    let path = source_path(&self.rewrites, source);
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+      match part {
+        "" | "." => {}
+        ".." => {
+          parts.pop()?;
+        }
+        part => parts.push(part),
+      }
+    }
+    // cat-file 按行读取对象名
+    (!parts.is_empty() && !path.contains('\n')).then(|| parts.join("/"))
+  }
+
+  fn read_blob(&self, path: &str) -> io::Result<Option<Vec<u8>>> {
+    let mut cat_file = self.cat_file.borrow_mut();
+    if cat_file.is_none() {
+      *cat_file = Some(CatFile::spawn(&self.repo)?);
+    }
+    let result = cat_file
+      .as_mut()
+      .unwrap()
+      .read(&format!("{}:{path}", self.commit));
+    if result.is_err() {
+      // 进程异常后下次重新启动
+      *cat_file = None;
+    }
+    result
+  }
+}
+
+impl SourceProvider for GitSourceRoot {
+  fn load_source(&self, source: &str) -> Option<String> {
+    let blob = self.read_blob(&self.resolve(source)?).ok()??;
+    String::from_utf8(blob).ok()
+  }
+}
+
+/// `git cat-file --batch` 子进程: 每行写入一个对象名, 读回 `<oid> <type> <size>` 与内容
+struct CatFile {
+  child: Child,
+  stdin: Option<ChildStdin>,
+  stdout: BufReader<ChildStdout>,
+}
+
+impl CatFile {
+  fn spawn(repo: &Path) -> io::Result<Self> {
+    let mut child = Command::new("git")
+      .arg("-C")
+      .arg(repo)
+      .args(["cat-file", "--batch"])
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::null())
+      .spawn()?;
+    let stdin = child.stdin.take();
+    let stdout = BufReader::new(child.stdout.take().expect("piped stdout"));
+    Ok(CatFile {
+      child,
+      stdin,
+      stdout,
+    })
+  }
+
+  /// 对象不存在或不是文件 (目录、子模块) 时为 None
+  fn read(&mut self, object: &str) -> io::Result<Option<Vec<u8>>> {
+    let stdin = self.stdin.as_mut().ok_or(io::ErrorKind::BrokenPipe)?;
+    writeln!(stdin, "{object}")?;
+    stdin.flush()?;
+    let mut header = String::new();
+    if self.stdout.read_line(&mut header)? == 0 {
+      return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    // `<name> missing` / `<name> ambiguous` 不带内容; 正常时为 `<oid> <type> <size>`
+    let header = header.trim_end();
+    if header.ends_with(" missing") || header.ends_with(" ambiguous") {
+      return Ok(None);
+    }
+    let mut fields = header.split(' ');
+    let (Some(_), Some(kind), Some(size)) = (fields.next(), fields.next(), fields.next()) else {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        header.to_string(),
+      ));
+    };
+    let size: usize = size
+      .parse()
+      .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, header.to_string()))?;
+    // 内容后跟一个换行
+    let mut content = vec![0; size + 1];
+    self.stdout.read_exact(&mut content)?;
+    content.pop();
+    Ok((kind == "blob").then_some(content))
+  }
+}
+
+impl Drop for CatFile {
+  fn drop(&mut self) {
+    // 关闭 stdin 后 git 自行退出
+    drop(self.stdin.take());
+    let _ = self.child.wait();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  fn git(repo: &Path, args: &[&str]) -> String {
+    let out = Command::new("git")
+      .arg("-C")
+      .arg(repo)
+      .args([
+        "-c",
+        "user.name=t",
+        "-c",
+        "user.email=t@t",
+        "-c",
+        "commit.gpgsign=false",
+      ])
+      .args(args)
+      .output()
+      .unwrap();
+    assert!(
+      out.status.success(),
+      "{}",
+      String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).unwrap()
+  }
+
+  #[test]
+  fn git_source_root_reads_committed_files() {
+    // 失败时也随 TempDir 一起删除
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path();
+    fs::create_dir_all(repo.join("src")).unwrap();
+    git(repo, &["init", "-q"]);
+    fs::write(repo.join("src/a.js"), "v1\n").unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", "v1"]);
+    git(repo, &["tag", "v1"]);
+    fs::write(repo.join("src/a.js"), "v2\n").unwrap();
+    git(repo, &["commit", "-q", "-am", "v2"]);
+    // 工作区的改动不影响读取
+    fs::write(repo.join("src/a.js"), "dirty\n").unwrap();
+
+    let v1 = GitSourceRoot::open(repo, "v1")
+      .unwrap()
+      .with_prefix_rewrite("webpack:///./", "");
+    assert_eq!(v1.commit(), git(repo, &["rev-parse", "v1"]).trim());
+    assert_eq!(
+      v1.load_source("webpack:///./src/a.js").as_deref(),
+      Some("v1\n")
+    );
+    assert_eq!(v1.load_source("src/missing.js"), None);
+    assert_eq!(v1.load_source("src"), None);
+    assert_eq!(v1.resolve("../outside.js"), None);
+    let head = GitSourceRoot::open(repo, "HEAD").unwrap();
+    assert_eq!(
+      head.load_source("dist/../src/a.js").as_deref(),
+      Some("v2\n")
+    );
+    assert!(GitSourceRoot::open(repo, "no-such-rev").is_err());
+  }
+}
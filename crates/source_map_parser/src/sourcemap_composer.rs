@@ -0,0 +1,90 @@
+use sourcemap::{SourceMap, SourceMapBuilder};
+
+/// 将按构建顺序排列的 source map 链 (如 TS -> Babel -> Terser) 合成为一份
+///
+/// `maps[0]` 为最先执行的转换 (其 sources 即最终原始源码), 最后一项对应最终产物。
+/// 最外层的每个 token 依次穿过内层 map 查找, 任一层在同一行找不到映射则丢弃该 token;
+/// 名称优先取最内层记录, sourcesContent 取自最内层 map。链为空时返回 None
+pub fn compose_sourcemaps(maps: &[SourceMap]) -> Option<SourceMap> {
+  let (outer, inners) = maps.split_last()?;
+  let mut builder = SourceMapBuilder::new(outer.get_file());
+  'tokens: for token in outer.tokens() {
+    if !token.has_source() {
+      continue;
+    }
+    let mut current = token;
+    let mut name = token.get_name();
+    for inner in inners.iter().rev() {
+      match inner.lookup_token(current.get_src_line(), current.get_src_col()) {
+        Some(t) if t.get_dst_line() == current.get_src_line() && t.has_source() => {
+          name = t.get_name().or(name);
+          current = t;
+        }
+        _ => continue 'tokens,
+      }
+    }
+    let raw = builder.add(
+      token.get_dst_line(),
+      token.get_dst_col(),
+      current.get_src_line(),
+      current.get_src_col(),
+      current.get_source(),
+      name,
+    );
+    if !builder.has_source_contents(raw.src_id) {
+      builder.set_source_contents(raw.src_id, current.get_source_view().map(|v| v.source()));
+    }
+  }
+  Some(builder.into_sourcemap())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// tokens: (dst_line, dst_col, src_line, src_col, name)
+  fn build(
+    src: &str,
+    contents: Option<&str>,
+    tokens: &[(u32, u32, u32, u32, Option<&str>)],
+  ) -> SourceMap {
+    let mut b = SourceMapBuilder::new(None);
+    for &(dl, dc, sl, sc, name) in tokens {
+      let raw = b.add(dl, dc, sl, sc, Some(src), name);
+      b.set_source_contents(raw.src_id, contents);
+    }
+    b.into_sourcemap()
+  }
+
+  #[test]
+  fn compose_three_stage_chain() {
+    let ts = build(
+      "src/a.ts",
+      Some("const greet = () => {\n  boom();\n};\n"),
+      &[(0, 0, 0, 6, Some("greet")), (0, 10, 1, 2, Some("boom"))],
+    );
+    let babel = build("a.js", None, &[(0, 0, 0, 0, None), (0, 5, 0, 10, None)]);
+    let terser = build(
+      "a.babel.js",
+      None,
+      &[(0, 0, 0, 0, Some("g")), (0, 3, 0, 5, Some("b"))],
+    );
+    let composed = compose_sourcemaps(&[ts, babel, terser]).unwrap();
+    let tok = composed.lookup_token(0, 3).unwrap();
+    assert_eq!(tok.get_source(), Some("src/a.ts"));
+    assert_eq!((tok.get_src_line(), tok.get_src_col()), (1, 2));
+    assert_eq!(tok.get_name(), Some("boom"));
+    let first = composed.lookup_token(0, 0).unwrap();
+    assert_eq!(first.get_name(), Some("greet"));
+    assert!(composed.get_source_contents(0).unwrap().contains("boom();"));
+  }
+
+  #[test]
+  fn compose_drops_tokens_unmapped_in_inner_map() {
+    let inner = build("a.ts", None, &[(1, 0, 5, 0, None)]);
+    let outer = build("a.js", None, &[(0, 0, 0, 0, None), (0, 4, 1, 0, None)]);
+    let composed = compose_sourcemaps(&[inner, outer]).unwrap();
+    assert_eq!(composed.get_token_count(), 1);
+    assert!(compose_sourcemaps(&[]).is_none());
+  }
+}
@@ -2,84 +2,604 @@ use once_cell::sync::Lazy;
 use regex::{Regex, RegexSet};
 use serde::Serialize;
 
+use crate::position::ColumnBase;
+
 static STACK_LINE_PRIMARY: Lazy<RegexSet> =
-  Lazy::new(|| RegexSet::new(&[r"^at ", r"@.+:\d+:\d+$"]).unwrap());
+  Lazy::new(|| RegexSet::new([r"^at ", r"@.+:\d+:\d+$"]).unwrap());
 
-static STACK_LINE_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+/// V8 / Node: `at name (url:l:c)` / `at url:l:c`
+static V8_STACK_LINE_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
   vec![
     Regex::new(r"^at\s+(?P<name>.+?)\s*\((?P<url>.+?):(?P<line>\d+):(?P<column>\d+)\)$").unwrap(),
-    Regex::new(r"^at\s+(?P<url>.+?):(?P<line>\d+):(?P<column>\d+)$").unwrap(),
-    Regex::new(r"^(?:async\s+)?(?P<name>[^@]+?)@(?P<url>.+?):(?P<line>\d+):(?P<column>\d+)$")
-      .unwrap(),
+    Regex::new(r"^at\s+(?P<async>async\s+)?(?P<url>.+?):(?P<line>\d+):(?P<column>\d+)$").unwrap(),
+  ]
+});
+
+/// Firefox (Safari 同构): `name@url:l:c` / `@url:l:c`
+static FIREFOX_STACK_LINE_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+  vec![
+    Regex::new(
+      r"^(?P<async>async\s+|async\*)?(?P<name>[^@]*?)@(?P<url>.+?):(?P<line>\d+):(?P<column>\d+)$",
+    )
+    .unwrap(),
+    Regex::new(r"^@(?P<url>.+?):(?P<line>\d+):(?P<column>\d+)$").unwrap(),
+  ]
+});
+
+/// 消息区 (第一个帧之前) 的 Firefox 规则: 函数名不得含空白, 以免 `Error: bad email a@b.com:1:2` 被当作帧
+static FIREFOX_STACK_LINE_STRICT: Lazy<Vec<Regex>> = Lazy::new(|| {
+  vec![
+    Regex::new(
+      r"^(?P<async>async\s+|async\*)?(?P<name>[^@\s]*?)@(?P<url>.+?):(?P<line>\d+):(?P<column>\d+)$",
+    )
+    .unwrap(),
     Regex::new(r"^@(?P<url>.+?):(?P<line>\d+):(?P<column>\d+)$").unwrap(),
   ]
 });
 
+/// 未锚定的兜底规则, 只在已出现帧之后使用
 static STACK_LINE_FALLBACK: Lazy<Regex> = Lazy::new(|| {
   Regex::new(r"at\s+(?P<name>.+?)?\s*\((?P<url>.+?):(?P<line>\d+):(?P<column>\d+)\)|at\s+(?P<url2>.+?):(?P<line2>\d+):(?P<column2>\d+)").unwrap()
 });
 
+/// Hermes (React Native) 字节码帧: `at foo (address at index.android.bundle:1:2345)`
+static HERMES_STACK_LINE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r"^at\s+(?P<name>.+?)\s*\((?P<bytecode>address at)\s+(?P<url>.+?):(?P<line>\d+):(?P<column>\d+)\)$")
+    .unwrap()
+});
+
+/// V8 WebAssembly 帧, 字节偏移为十六进制: `at foo (https://x/app.wasm:wasm-function[42]:0x1a3f)`
+static WASM_V8_STACK_LINE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(
+    r"^at\s+(?:(?P<name>.+?)\s+\()?(?P<url>.+?):wasm-function\[(?P<wasm_function>\d+)\]:0x(?P<wasm_offset>[0-9a-fA-F]+)\)?$",
+  )
+  .unwrap()
+});
+
+/// Firefox WebAssembly 帧 `foo@https://x/app.wasm:wasm-function[42]:0x1a3f` 与裸帧 `wasm-function[42]:0x1a3f`
+static WASM_FIREFOX_STACK_LINE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(
+    r"^(?:(?P<name>[^@]*)@)?(?:(?P<url>.+?):)?wasm-function\[(?P<wasm_function>\d+)\]:0x(?P<wasm_offset>[0-9a-fA-F]+)$",
+  )
+  .unwrap()
+});
+
+/// Safari WebAssembly 帧, 不带偏移: `<?>.wasm-function[42]@[wasm code]`
+static WASM_JSC_STACK_LINE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r"^(?P<name>(?:[^@]*?\.)?wasm-function\[(?P<wasm_function>\d+)\])@\[wasm code\]$")
+    .unwrap()
+});
+
+/// V8 无位置帧: `at Array.forEach (<anonymous>)` / `at JSON.parse (native)` /
+/// `at async Promise.all (index 0)`
+static V8_STACK_LINE_NO_POSITION: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(
+    r"^at\s+(?P<name>.+?)\s*\((?:(?P<url>native|<anonymous>)|index (?P<promise_index>\d+))\)$",
+  )
+  .unwrap()
+});
+
+/// JavaScriptCore (Safari) 特有帧, 需先于通用规则匹配
+static JSC_STACK_LINE_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+  vec![
+    // `[native code]` / `forEach@[native code]`
+    Regex::new(r"^(?:(?P<name>[^@]*)@)?(?P<url>\[native code\])$").unwrap(),
+    // `global code@url:l:c` / `eval code` / `module code@url:l`
+    Regex::new(
+      r"^(?P<name>(?:global|eval|module) code)(?:@(?P<url>.+?):(?P<line>\d+)(?::(?P<column>\d+))?)?$",
+    )
+    .unwrap(),
+  ]
+});
+
+/// 通用规则均未命中时再尝试: JSC 无列号帧 `name@url:line` 与无名帧 `url:line:column`
+///
+/// 无名帧必须带列号, 否则消息中单独成行的 `/usr/lib/x:12`、`https://host/api:8080` 会被当作帧
+static JSC_STACK_LINE_LOOSE: Lazy<Vec<Regex>> = Lazy::new(|| {
+  vec![
+    Regex::new(r"^(?P<name>[^@\s]*)@(?P<url>.+):(?P<line>\d+)$").unwrap(),
+    Regex::new(r"^(?P<url>(?:[a-zA-Z][\w+.-]*://|/)[^\s@()]+?):(?P<line>\d+):(?P<column>\d+)$")
+      .unwrap(),
+  ]
+});
+
+/// JSC 无名且无列号的帧 `url:line`, 与普通文本难以区分, 仅在显式指定 Safari 时识别
+static JSC_STACK_LINE_BARE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r"^(?P<url>(?:[a-zA-Z][\w+.-]*://|/)[^\s@()]+?):(?P<line>\d+)$").unwrap()
+});
+
+/// V8 eval 帧: `at eval (eval at compile (https://x/app.js:10:5), <anonymous>:3:4)`
+static V8_EVAL_LINE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(
+    r"^at\s+(?:(?P<name>.*?)\s+)?\(eval at (?P<origin>.+), (?P<script>[^,]+?):(?P<line>\d+):(?P<column>\d+)\)$",
+  )
+  .unwrap()
+});
+
+/// eval 来源的一层: `compile (https://x/app.js:10:5)` 或 `<anonymous> (eval at ...)`
+static V8_EVAL_ORIGIN: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"^(?P<name>.+?) \((?P<inner>.+)\)$").unwrap());
+
+static V8_EVAL_LOCATION: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"^(?P<url>.+):(?P<line>\d+):(?P<column>\d+)$").unwrap());
+
+/// V8 为没有 `//# sourceURL` 的 eval 代码使用的脚本名
+const V8_ANONYMOUS_SCRIPT: &str = "<anonymous>";
+
+/// 表示引擎内置函数的位置标记
+const NATIVE_LOCATIONS: &[&str] = &["native", "<anonymous>"];
+
+const JSC_NATIVE_CODE: &str = "[native code]";
+const JSC_EVAL_CODE: &str = "eval code";
+
 #[derive(Clone, Debug, Serialize)]
 pub struct Stack<'a> {
+  /// 函数名, 已去除 `async `/`new ` 前缀与 `[as alias]` 后缀
   pub name: &'a str,
+  /// 编译后 (1-based) 行号, 帧不带位置时为 0
   pub line: u32,
+  /// 编译后列号, 帧不带列号时为 0; 基准随引擎而定, 查询时用 [`Stack::zero_based_column`] 换算
   pub column: u32,
   pub source_file: &'a str,
   pub original_raw: &'a str,
+  /// 引擎内置函数帧 (如 Safari `[native code]` / V8 `(native)`), 无法通过 source map 还原
+  pub is_native: bool,
+  /// 异步帧 (V8 `at async foo` / Firefox `async*foo@`)
+  pub is_async: bool,
+  /// 构造调用帧 (V8 `at new Foo`)
+  pub is_constructor: bool,
+  /// 方法调用的接收者类型, 如 `Foo.bar` 中的 `Foo`
+  pub type_name: Option<&'a str>,
+  /// 方法名, 如 `Foo.bar` 中的 `bar`
+  pub method_name: Option<&'a str>,
+  /// V8 `[as alias]` 中的调用别名
+  pub method_alias: Option<&'a str>,
+  /// V8 `Promise.all (index N)` 中的 N
+  pub promise_index: Option<u32>,
+  /// WebAssembly 帧的函数下标 `wasm-function[N]`; 此时 `line` 固定为 1, `column` 为模块内字节偏移
+  pub wasm_function: Option<u32>,
+  /// eval 执行的代码帧 (如 Safari `eval code` / V8 `eval at ...`)
+  pub is_eval: bool,
+  /// V8 eval 帧的来源链, 仅 V8 eval 帧存在
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub eval_origin: Option<EvalOrigin>,
+  /// `column` 为 0-based 偏移而非堆栈列号 (wasm 字节偏移 / Hermes `address at` 字节码偏移)
+  #[serde(skip)]
+  pub column_is_offset: bool,
 }
 
-pub fn parse_stack_line(original_raw: &str) -> Option<Stack<'_>> {
-  let trimmed = original_raw.trim();
-  if trimmed.matches(':').count() < 2 {
-    return None;
-  }
-  if STACK_LINE_PRIMARY.is_match(trimmed) {
-    for re in STACK_LINE_PATTERNS.iter() {
-      if let Some(caps) = re.captures(trimmed) {
-        let name = caps.name("name").map(|m| m.as_str()).unwrap_or("");
-        let file = caps.name("url").map(|m| m.as_str()).unwrap_or("");
-        let line = caps
-          .name("line")
-          .and_then(|m| m.as_str().parse::<u32>().ok())
-          .unwrap_or(0);
-        let column = caps
-          .name("column")
-          .and_then(|m| m.as_str().parse::<u32>().ok())
-          .unwrap_or(0);
-        return Some(Stack {
-          name,
-          line,
-          column,
-          source_file: file,
-          original_raw: trimmed,
-        });
-      }
+/// V8 eval 帧的来源信息
+///
+/// 无 `//# sourceURL` 时, 所属 [`Stack`] 的 `source_file`/`line`/`column` 取打包产物中
+/// 最外层 eval 调用的位置 (可映射); 有 sourceURL 时取 sourceURL 与 eval 代码内的位置
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct EvalOrigin {
+  /// 打包产物中调用 eval 的函数名 (嵌套 eval 时取最外层)
+  pub function_name: String,
+  /// 最外层 eval 调用所在文件
+  pub source_file: String,
+  pub line: u32,
+  pub column: u32,
+  /// eval 代码内的 (1-based) 行号
+  pub eval_line: u32,
+  /// eval 代码内的列号
+  pub eval_column: u32,
+  /// eval 代码 `//# sourceURL` 声明的名称
+  pub source_url: Option<String>,
+}
+
+/// 帧未能映射的原因, 序列化为 `{ "kind": ... }`; 查询失败时 `kind` 与字段同 [`crate::LookupError`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FrameFailure {
+  /// 引擎内置函数帧
+  #[error("native frame")]
+  NativeFrame,
+  /// 无来源位置的 eval 帧
+  #[error("eval frame")]
+  EvalFrame,
+  /// 帧不带行号
+  #[error("frame has no position")]
+  NoPosition,
+  /// 没有可用于该帧的 source map
+  #[error("no sourcemap for frame")]
+  NoSourceMap,
+  /// source map 查询失败
+  #[error(transparent)]
+  #[serde(untagged)]
+  Lookup(crate::LookupError),
+}
+
+/// 帧的结构化属性与解析出的编译后位置, 随映射结果一同返回
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct FrameInfo {
+  pub name: String,
+  /// 编译后文件, 与 [`Stack::source_file`] 相同
+  pub source_file: String,
+  /// 编译后 (1-based) 行号, 帧不带位置时为 0
+  pub line: u32,
+  /// 编译后列号, 按输入原样保留 (未换算基准)
+  pub column: u32,
+  pub is_async: bool,
+  pub is_constructor: bool,
+  pub is_native: bool,
+  pub is_eval: bool,
+  pub type_name: Option<String>,
+  pub method_name: Option<String>,
+  pub method_alias: Option<String>,
+  pub promise_index: Option<u32>,
+  pub wasm_function: Option<u32>,
+  /// V8 eval 帧的来源链, 仅 V8 eval 帧存在
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub eval_origin: Option<EvalOrigin>,
+}
+
+impl From<&Stack<'_>> for FrameInfo {
+  fn from(st: &Stack<'_>) -> Self {
+    FrameInfo {
+      name: st.name.to_string(),
+      source_file: st.source_file.to_string(),
+      line: st.line,
+      column: st.column,
+      is_async: st.is_async,
+      is_constructor: st.is_constructor,
+      is_native: st.is_native,
+      is_eval: st.is_eval,
+      type_name: st.type_name.map(str::to_string),
+      method_name: st.method_name.map(str::to_string),
+      method_alias: st.method_alias.map(str::to_string),
+      promise_index: st.promise_index,
+      wasm_function: st.wasm_function,
+      eval_origin: st.eval_origin.clone(),
     }
   }
-  if let Some(captures) = STACK_LINE_FALLBACK.captures(trimmed) {
-    let name = captures.name("name").map(|m| m.as_str()).unwrap_or("");
-    let url = captures.name("url");
-    let url2 = captures.name("url2");
-    let file = url.or(url2).map(|m| m.as_str()).unwrap_or("");
-    let line = captures
+}
+
+/// 从原始函数名中拆出的各部分
+struct FunctionNameParts<'a> {
+  name: &'a str,
+  is_async: bool,
+  is_constructor: bool,
+  type_name: Option<&'a str>,
+  method_name: Option<&'a str>,
+  method_alias: Option<&'a str>,
+}
+
+/// 拆解 V8 风格函数名: `async new Foo.bar [as baz]`
+fn decompose_function_name(raw: &str) -> FunctionNameParts<'_> {
+  let mut name = raw.trim();
+  let is_async = match name.strip_prefix("async ") {
+    Some(rest) => {
+      name = rest.trim_start();
+      true
+    }
+    None => false,
+  };
+  let is_constructor = match name.strip_prefix("new ") {
+    Some(rest) => {
+      name = rest.trim_start();
+      true
+    }
+    None => false,
+  };
+  let mut method_alias = None;
+  if let Some((head, alias)) = name.strip_suffix(']').and_then(|n| n.rsplit_once(" [as ")) {
+    name = head;
+    method_alias = Some(alias);
+  }
+  let (type_name, method_name) = match name.rsplit_once('.') {
+    Some((ty, method)) if !ty.is_empty() && !method.is_empty() => (Some(ty), Some(method)),
+    _ => (None, None),
+  };
+  FunctionNameParts {
+    name,
+    is_async,
+    is_constructor,
+    type_name,
+    method_name,
+    method_alias,
+  }
+}
+
+impl<'a> Stack<'a> {
+  fn from_captures(caps: &regex::Captures<'a>, original_raw: &'a str) -> Self {
+    let parts = decompose_function_name(caps.name("name").map(|m| m.as_str()).unwrap_or(""));
+    let source_file = caps
+      .name("url")
+      .or(caps.name("url2"))
+      .map(|m| m.as_str())
+      .unwrap_or("");
+    let line = caps
       .name("line")
-      .or(captures.name("line2"))
+      .or(caps.name("line2"))
       .and_then(|m| m.as_str().parse::<u32>().ok())
       .unwrap_or(0);
-    let column = captures
+    let column = caps
       .name("column")
-      .or(captures.name("column2"))
+      .or(caps.name("column2"))
       .and_then(|m| m.as_str().parse::<u32>().ok())
       .unwrap_or(0);
-    return Some(Stack {
-      name,
+    // wasm source map 的映射全部位于第 1 行, 字节偏移即列号
+    let wasm_offset = caps
+      .name("wasm_offset")
+      .and_then(|m| u32::from_str_radix(m.as_str(), 16).ok());
+    let (line, column) = match wasm_offset {
+      Some(offset) => (1, offset),
+      None => (line, column),
+    };
+    Stack {
+      name: parts.name,
+      line,
+      column,
+      source_file,
+      original_raw,
+      is_native: source_file == JSC_NATIVE_CODE
+        || (line == 0 && NATIVE_LOCATIONS.contains(&source_file)),
+      is_async: parts.is_async || caps.name("async").is_some(),
+      is_constructor: parts.is_constructor,
+      type_name: parts.type_name,
+      method_name: parts.method_name,
+      method_alias: parts.method_alias,
+      promise_index: caps
+        .name("promise_index")
+        .and_then(|m| m.as_str().parse::<u32>().ok()),
+      wasm_function: caps
+        .name("wasm_function")
+        .and_then(|m| m.as_str().parse::<u32>().ok()),
+      is_eval: parts.name == JSC_EVAL_CODE,
+      eval_origin: None,
+      column_is_offset: wasm_offset.is_some() || caps.name("bytecode").is_some(),
+    }
+  }
+
+  fn from_v8_eval(original_raw: &'a str) -> Option<Self> {
+    let caps = V8_EVAL_LINE.captures(original_raw)?;
+    let mut origin = caps.name("origin")?.as_str();
+    // 逐层剥离嵌套的 `eval at`, 直到出现真实文件位置
+    let (function_name, location) = loop {
+      let layer = V8_EVAL_ORIGIN.captures(origin)?;
+      let inner = layer.name("inner")?.as_str();
+      match inner.strip_prefix("eval at ") {
+        Some(rest) => origin = rest,
+        None => break (layer.name("name")?.as_str(), inner),
+      }
+    };
+    let loc = V8_EVAL_LOCATION.captures(location)?;
+    let parse_u32 = |c: &regex::Captures, key: &str| {
+      c.name(key)
+        .and_then(|m| m.as_str().parse::<u32>().ok())
+        .unwrap_or(0)
+    };
+    let script = caps.name("script")?.as_str();
+    let source_url = (script != V8_ANONYMOUS_SCRIPT).then_some(script);
+    let origin_file = loc.name("url")?.as_str();
+    let (origin_line, origin_column) = (parse_u32(&loc, "line"), parse_u32(&loc, "column"));
+    let (eval_line, eval_column) = (parse_u32(&caps, "line"), parse_u32(&caps, "column"));
+    let (source_file, line, column) = match source_url {
+      Some(url) => (url, eval_line, eval_column),
+      None => (origin_file, origin_line, origin_column),
+    };
+    let eval_origin = EvalOrigin {
+      function_name: function_name.to_string(),
+      source_file: origin_file.to_string(),
+      line: origin_line,
+      column: origin_column,
+      eval_line,
+      eval_column,
+      source_url: source_url.map(str::to_string),
+    };
+    let parts = decompose_function_name(caps.name("name").map(|m| m.as_str()).unwrap_or(""));
+    Some(Stack {
+      name: parts.name,
       line,
       column,
-      source_file: file,
-      original_raw: trimmed,
-    });
+      source_file,
+      original_raw,
+      is_native: false,
+      is_async: parts.is_async,
+      is_constructor: parts.is_constructor,
+      type_name: parts.type_name,
+      method_name: parts.method_name,
+      method_alias: parts.method_alias,
+      promise_index: None,
+      wasm_function: None,
+      is_eval: true,
+      eval_origin: Some(eval_origin),
+      column_is_offset: false,
+    })
+  }
+
+  /// 0-based 查询列: 普通帧的列号按 `frame_base` 换算 (V8 / Firefox / Safari 堆栈为 1-based),
+  /// wasm 与 Hermes 字节码帧的列号本身即 0-based 偏移
+  pub fn zero_based_column(&self, frame_base: ColumnBase) -> u32 {
+    if self.column_is_offset {
+      self.column
+    } else {
+      frame_base.to_zero_based(self.column)
+    }
+  }
+
+  /// 帧无法通过 source map 查询的原因, 可查询时为 None
+  pub fn unmappable_reason(&self) -> Option<FrameFailure> {
+    if self.is_native {
+      Some(FrameFailure::NativeFrame)
+    } else if self.is_eval && self.eval_origin.is_none() {
+      Some(FrameFailure::EvalFrame)
+    } else if self.line == 0 {
+      Some(FrameFailure::NoPosition)
+    } else {
+      None
+    }
+  }
+
+  /// 是否带有可用于 source map 查询的位置
+  pub fn is_mappable(&self) -> bool {
+    self.unmappable_reason().is_none()
+  }
+}
+
+/// 堆栈来源引擎
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StackEngine {
+  V8,
+  Firefox,
+  Safari,
+  /// V8 语法, 额外带 `node:` / `file://` / `internal/` 等 Node 路径
+  Node,
+  /// React Native Hermes, V8 语法并带 `address at` 字节码位置
+  Hermes,
+}
+
+fn match_line<'a>(re: &Regex, trimmed: &'a str) -> Option<Stack<'a>> {
+  re.captures(trimmed)
+    .map(|caps| Stack::from_captures(&caps, trimmed))
+}
+
+fn first_match<'a>(patterns: &[Regex], trimmed: &'a str) -> Option<Stack<'a>> {
+  patterns.iter().find_map(|re| match_line(re, trimmed))
+}
+
+fn parse_v8_line(trimmed: &str) -> Option<Stack<'_>> {
+  if let Some(stack) = Stack::from_v8_eval(trimmed) {
+    return Some(stack);
+  }
+  if let Some(stack) = match_line(&WASM_V8_STACK_LINE, trimmed) {
+    return Some(stack);
+  }
+  if let Some(caps) = V8_STACK_LINE_NO_POSITION.captures(trimmed) {
+    return Some(Stack::from_captures(&caps, trimmed));
+  }
+  first_match(&V8_STACK_LINE_PATTERNS, trimmed)
+}
+
+fn parse_hermes_line(trimmed: &str) -> Option<Stack<'_>> {
+  if let Some(caps) = HERMES_STACK_LINE.captures(trimmed) {
+    return Some(Stack::from_captures(&caps, trimmed));
+  }
+  parse_v8_line(trimmed)
+}
+
+fn firefox_patterns(strict: bool) -> &'static [Regex] {
+  if strict {
+    &FIREFOX_STACK_LINE_STRICT
+  } else {
+    &FIREFOX_STACK_LINE_PATTERNS
+  }
+}
+
+fn parse_safari_line(trimmed: &str, strict: bool) -> Option<Stack<'_>> {
+  match_line(&WASM_JSC_STACK_LINE, trimmed)
+    .or_else(|| first_match(&JSC_STACK_LINE_PATTERNS, trimmed))
+    .or_else(|| first_match(firefox_patterns(strict), trimmed))
+    .or_else(|| first_match(&JSC_STACK_LINE_LOOSE, trimmed))
+    .or_else(|| match_line(&JSC_STACK_LINE_BARE, trimmed))
+}
+
+/// 按指定引擎的语法解析单行, 不做跨引擎回退
+pub fn parse_stack_line_as(original_raw: &str, engine: StackEngine) -> Option<Stack<'_>> {
+  parse_line_as(original_raw.trim(), engine, false)
+}
+
+/// `strict` 时只接受锚定且不会误吞消息文本的规则, 用于第一个帧之前的消息区
+fn parse_line_as(trimmed: &str, engine: StackEngine, strict: bool) -> Option<Stack<'_>> {
+  match engine {
+    StackEngine::V8 | StackEngine::Node => parse_v8_line(trimmed),
+    StackEngine::Hermes => parse_hermes_line(trimmed),
+    StackEngine::Firefox => match_line(&WASM_FIREFOX_STACK_LINE, trimmed)
+      .or_else(|| first_match(firefox_patterns(strict), trimmed)),
+    StackEngine::Safari => parse_safari_line(trimmed, strict),
+  }
+}
+
+/// 按指定引擎的语法逐行解析, 无法识别的行被丢弃
+pub fn parse_stack_trace_as(trace_string: &str, engine: StackEngine) -> Vec<Stack<'_>> {
+  trace_string
+    .lines()
+    .filter_map(|l| parse_stack_line_as(l, engine))
+    .collect()
+}
+
+/// 根据帧特征识别堆栈来源引擎, 没有任何可识别的帧时返回 None
+///
+/// 特征越具体优先级越高: Hermes > Node > V8, Safari > Firefox
+pub fn detect_engine(trace_string: &str) -> Option<StackEngine> {
+  let mut detected: Option<StackEngine> = None;
+  let mut promote = |engine: StackEngine| {
+    let rank = |e: StackEngine| match e {
+      StackEngine::Hermes | StackEngine::Safari => 2,
+      StackEngine::Node => 1,
+      StackEngine::V8 | StackEngine::Firefox => 0,
+    };
+    if detected.is_none_or(|d| rank(engine) > rank(d)) {
+      detected = Some(engine);
+    }
+  };
+  for line in trace_string.lines() {
+    let trimmed = line.trim();
+    if HERMES_STACK_LINE.is_match(trimmed) {
+      promote(StackEngine::Hermes);
+    } else if let Some(stack) = parse_v8_line(trimmed) {
+      let file = stack.source_file;
+      if file.starts_with("node:") || file.starts_with("file://") || file.starts_with("internal/") {
+        promote(StackEngine::Node);
+      } else {
+        promote(StackEngine::V8);
+      }
+    } else if WASM_JSC_STACK_LINE.is_match(trimmed)
+      || first_match(&JSC_STACK_LINE_PATTERNS, trimmed).is_some()
+    {
+      promote(StackEngine::Safari);
+    } else if WASM_FIREFOX_STACK_LINE.is_match(trimmed)
+      || first_match(&FIREFOX_STACK_LINE_STRICT, trimmed).is_some()
+    {
+      promote(StackEngine::Firefox);
+    } else if first_match(&JSC_STACK_LINE_LOOSE, trimmed).is_some() {
+      promote(StackEngine::Safari);
+    }
+  }
+  detected
+}
+
+/// 自动识别单行格式 (逐行兼容 V8 / Firefox / Safari / Hermes 混合输入)
+pub fn parse_stack_line(original_raw: &str) -> Option<Stack<'_>> {
+  parse_line(original_raw.trim(), false)
+}
+
+/// 同 [`parse_stack_line`]; `strict` 时不使用未锚定的兜底规则, Firefox 帧名不得含空白
+fn parse_line(trimmed: &str, strict: bool) -> Option<Stack<'_>> {
+  if let Some(stack) = Stack::from_v8_eval(trimmed) {
+    return Some(stack);
+  }
+  for re in [
+    &*WASM_V8_STACK_LINE,
+    &*WASM_FIREFOX_STACK_LINE,
+    &*WASM_JSC_STACK_LINE,
+    &*HERMES_STACK_LINE,
+    &*V8_STACK_LINE_NO_POSITION,
+  ] {
+    if let Some(stack) = match_line(re, trimmed) {
+      return Some(stack);
+    }
   }
-  None
+  if let Some(stack) = first_match(&JSC_STACK_LINE_PATTERNS, trimmed) {
+    return Some(stack);
+  }
+  if trimmed.matches(':').count() >= 2 {
+    if STACK_LINE_PRIMARY.is_match(trimmed) {
+      if let Some(stack) = first_match(&V8_STACK_LINE_PATTERNS, trimmed)
+        .or_else(|| first_match(firefox_patterns(strict), trimmed))
+      {
+        return Some(stack);
+      }
+    }
+    if !strict {
+      if let Some(caps) = STACK_LINE_FALLBACK.captures(trimmed) {
+        return Some(Stack::from_captures(&caps, trimmed));
+      }
+    }
+  }
+  first_match(&JSC_STACK_LINE_LOOSE, trimmed)
 }
 
 pub fn parse_stack_trace(trace_string: &str) -> Vec<Stack<'_>> {
@@ -89,29 +609,162 @@ pub fn parse_stack_trace(trace_string: &str) -> Vec<Stack<'_>> {
     .collect()
 }
 
+/// 错误的 cause 段落: Node `[cause]: Error: ...` / 通用 `Caused by: Error: ...`
+static CAUSE_MARKER: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"^(?:\[cause\]:|Caused by:)\s*(?P<message>.*)$").unwrap());
+
+/// Node AggregateError 子错误列表的开始: `[errors]: [`
+static ERRORS_MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[errors\]:\s*\[$").unwrap());
+
 #[derive(Debug, Serialize)]
 pub struct ErrorStack<'a> {
+  /// 本错误对应的原始文本段 (根错误为完整输入)
   pub error_raw: &'a str,
   pub stacks: Vec<Stack<'a>>,
+  /// 第一个帧之前的全部消息行 (可能跨多行); Firefox / Safari 的 `error.stack` 不含消息, 此时为空
   pub error_message: String,
+  /// `[cause]:` / `Caused by:` 段落解析出的下一级错误
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub cause: Option<Box<ErrorStack<'a>>>,
+  /// AggregateError 的子错误, 按输出顺序
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub errors: Vec<ErrorStack<'a>>,
 }
 
-impl ErrorStack<'_> {
-  pub fn from_raw(error_raw: &str) -> ErrorStack<'_> {
-    let mut stacks: Vec<Stack> = Vec::new();
-    let mut error_message = String::new();
-    for (index, line) in error_raw.lines().enumerate() {
-      if index == 0 {
-        error_message = line.to_string();
-      } else if let Some(stack) = parse_stack_line(line.trim()) {
-        stacks.push(stack);
-      }
-    }
+impl<'a> ErrorStack<'a> {
+  pub fn from_raw(error_raw: &'a str) -> ErrorStack<'a> {
+    Self::parse(error_raw, None)
+  }
+
+  /// 同 [`Self::from_raw`], 帧行只按 `engine` 的语法解析 (见 [`parse_stack_line_as`])
+  pub fn from_raw_as(error_raw: &'a str, engine: StackEngine) -> ErrorStack<'a> {
+    Self::parse(error_raw, Some(engine))
+  }
+
+  fn parse(error_raw: &'a str, engine: Option<StackEngine>) -> ErrorStack<'a> {
+    let mut parser = ErrorStackParser {
+      raw: error_raw,
+      lines: error_raw.lines().collect(),
+      pos: 0,
+      engine,
+    };
+    let mut root = parser.parse_error(String::new(), 0, false);
+    root.error_raw = error_raw;
+    root
+  }
+
+  fn empty(error_raw: &'a str, error_message: String) -> Self {
     ErrorStack {
       error_raw,
-      stacks,
+      stacks: Vec::new(),
       error_message,
+      cause: None,
+      errors: Vec::new(),
+    }
+  }
+
+  /// 深度优先列出本错误及所有 cause / 子错误的帧
+  pub fn all_stacks(&self) -> Vec<&Stack<'a>> {
+    let mut out: Vec<&Stack<'a>> = self.stacks.iter().collect();
+    if let Some(cause) = &self.cause {
+      out.extend(cause.all_stacks());
+    }
+    for err in &self.errors {
+      out.extend(err.all_stacks());
+    }
+    out
+  }
+}
+
+/// 按行解析错误树: 每个错误从消息行开始, 收集其后的帧, 遇到 cause / 子错误列表时递归
+struct ErrorStackParser<'a> {
+  raw: &'a str,
+  lines: Vec<&'a str>,
+  pos: usize,
+  /// 显式指定的引擎, None 时逐行兼容各引擎
+  engine: Option<StackEngine>,
+}
+
+impl<'a> ErrorStackParser<'a> {
+  /// 解析一个错误, `start` 为该错误首行的下标; `error_message` 为调用方已取出的消息首行 (可为空)
+  ///
+  /// `in_list` 为 true 表示位于 `[errors]: [ ... ]` 中: 已有帧后出现的非帧行视为下一个兄弟错误的消息,
+  /// `]` 结束列表; 两种情况都不消费该行, 交由上层处理
+  fn parse_error(&mut self, error_message: String, start: usize, in_list: bool) -> ErrorStack<'a> {
+    let mut error = ErrorStack::empty("", error_message);
+    while let Some(line) = self.lines.get(self.pos).copied() {
+      let trimmed = line.trim();
+      if let Some(caps) = CAUSE_MARKER.captures(trimmed) {
+        let cause_start = self.pos;
+        self.pos += 1;
+        let message = caps["message"].to_string();
+        error.cause = Some(Box::new(self.parse_error(message, cause_start, in_list)));
+        continue;
+      }
+      if ERRORS_MARKER.is_match(trimmed) {
+        self.pos += 1;
+        self.parse_error_list(&mut error.errors);
+        continue;
+      }
+      // Node inspect 在帧行末尾追加 ` {` 以展开 cause 等属性
+      let frame_text = trimmed.trim_end_matches(['{', ',']).trim_end();
+      // 第一个帧之前只用严格规则, 消息中夹带的 `at x (a.js:1:2)`、`a@b.com:1:2` 不会被当作帧
+      let strict = error.stacks.is_empty();
+      let stack = match self.engine {
+        Some(engine) => parse_line_as(frame_text, engine, strict),
+        None => parse_line(frame_text, strict),
+      };
+      if let Some(stack) = stack {
+        error.stacks.push(stack);
+        self.pos += 1;
+        continue;
+      }
+      // 第一个帧之前的非帧行都属于消息 (多行消息 / JSON / 断言 diff)
+      let in_message = error.stacks.is_empty() && error.cause.is_none() && error.errors.is_empty();
+      let is_closer = matches!(trimmed, "}" | "},");
+      if in_list && (trimmed.starts_with(']') || (!is_closer && !in_message)) {
+        break;
+      }
+      if in_message && !(error.error_message.is_empty() && trimmed.is_empty()) {
+        if !error.error_message.is_empty() {
+          error.error_message.push('\n');
+        }
+        error.error_message.push_str(line.trim_end());
+      }
+      self.pos += 1;
+    }
+    let message_len = error.error_message.trim_end().len();
+    error.error_message.truncate(message_len);
+    error.error_raw = self.span(start, self.pos);
+    error
+  }
+
+  /// 解析 `[errors]: [` 之后的子错误, 消费到对应的 `]`
+  fn parse_error_list(&mut self, errors: &mut Vec<ErrorStack<'a>>) {
+    while let Some(line) = self.lines.get(self.pos).copied() {
+      let trimmed = line.trim();
+      let start = self.pos;
+      self.pos += 1;
+      if trimmed.starts_with(']') {
+        return;
+      }
+      if trimmed.is_empty() || matches!(trimmed, "}" | "},") {
+        continue;
+      }
+      errors.push(self.parse_error(trimmed.to_string(), start, true));
+    }
+  }
+
+  /// `lines[start..end]` 在原始文本中对应的连续切片
+  fn span(&self, start: usize, end: usize) -> &'a str {
+    if end <= start {
+      return "";
     }
+    let base = self.raw.as_ptr() as usize;
+    let from = self.lines[start].as_ptr() as usize - base;
+    let last = self.lines[end - 1];
+    let to = last.as_ptr() as usize - base + last.len();
+    &self.raw[from..to]
   }
 }
 
@@ -121,7 +774,7 @@ mod tests {
 
   #[test]
   fn parse_various_stack_lines() {
-    let lines = vec![
+    let lines = [
       "at foo (https://example.com/app.js:10:5)",
       "at https://example.com/app.js:20:15",
       "async bar@https://example.com/app.js:30:25",
@@ -131,8 +784,9 @@ mod tests {
     assert_eq!(stacks.len(), lines.len());
     assert_eq!(stacks[0].line, 10);
     assert_eq!(stacks[1].column, 15);
-    // The async prefix is ignored by the regex, only the method name is kept
+    // The async prefix is stripped from the name and recorded as a flag
     assert_eq!(stacks[2].name, "bar");
+    assert!(stacks[2].is_async);
     assert_eq!(stacks[3].line, 40);
   }
 
@@ -153,6 +807,305 @@ mod tests {
     assert_eq!(es.stacks[0].line, 3);
   }
 
+  #[test]
+  fn parse_error_stack_without_message() {
+    let raw = "load@https://a/app.js:3:7\n@https://a/app.js:9:1\n";
+    let es = ErrorStack::from_raw(raw);
+    assert_eq!(es.error_message, "");
+    assert_eq!(es.stacks.len(), 2);
+    assert_eq!(es.stacks[0].name, "load");
+  }
+
+  #[test]
+  fn parse_error_stack_multiline_message() {
+    let raw = "AssertionError: expected\n\n  { \"a\": 1 }\n- actual\n+ expected\n\n    at check (https://a/app.js:4:2)\n    at https://a/app.js:5:1";
+    let es = ErrorStack::from_raw(raw);
+    assert_eq!(
+      es.error_message,
+      "AssertionError: expected\n\n  { \"a\": 1 }\n- actual\n+ expected"
+    );
+    assert_eq!(es.stacks.len(), 2);
+  }
+
+  #[test]
+  fn parse_error_stack_message_with_path_and_url() {
+    let raw = "Error: failed to open /usr/lib/x:12
+/usr/lib/x:12
+fetch https://host/api:8080
+https://host/api:8080
+    at load (https://a/app.js:4:2)";
+    let es = ErrorStack::from_raw(raw);
+    assert_eq!(
+      es.error_message,
+      "Error: failed to open /usr/lib/x:12
+/usr/lib/x:12
+fetch https://host/api:8080
+https://host/api:8080"
+    );
+    assert_eq!(es.stacks.len(), 1);
+    assert_eq!(es.stacks[0].name, "load");
+    assert!(parse_stack_line("https://host/api:8080").is_none());
+
+    let raw = "Error: request failed at fetchUser (api.js:10:5) with 500
+    at main (https://a/min.js:1:10)";
+    let es = ErrorStack::from_raw(raw);
+    assert_eq!(
+      es.error_message,
+      "Error: request failed at fetchUser (api.js:10:5) with 500"
+    );
+    assert_eq!(es.stacks.len(), 1);
+    assert_eq!(es.stacks[0].name, "main");
+
+    let raw = "Error: bad email foo@example.com:1:2
+    at main (https://a/min.js:1:10)";
+    let es = ErrorStack::from_raw(raw);
+    assert_eq!(es.error_message, "Error: bad email foo@example.com:1:2");
+    assert_eq!(es.stacks.len(), 1);
+    assert_eq!(detect_engine(raw), Some(StackEngine::V8));
+    let es = ErrorStack::from_raw_as(
+      "Error: bad email foo@example.com:1:2\n@https://a/min.js:1:10",
+      StackEngine::Firefox,
+    );
+    assert_eq!(es.error_message, "Error: bad email foo@example.com:1:2");
+    assert_eq!(es.stacks.len(), 1);
+    assert_eq!(
+      detect_engine(
+        "Error: x
+/usr/lib/x:12"
+      ),
+      None
+    );
+  }
+
+  #[test]
+  fn parse_error_cause_chain() {
+    let raw = "Error: outer\n    at a (https://x/app.js:1:1) {\n  [cause]: TypeError: inner\n      at b (https://x/app.js:2:2)\n      ... 3 lines matching cause stack trace ...\n}\nCaused by: RangeError: root\n    at c (https://x/app.js:3:3)";
+    let es = ErrorStack::from_raw(raw);
+    assert_eq!(es.error_message, "Error: outer");
+    assert_eq!(es.stacks.len(), 1);
+    let cause = es.cause.as_ref().unwrap();
+    assert_eq!(cause.error_message, "TypeError: inner");
+    assert_eq!(cause.stacks[0].name, "b");
+    assert!(cause.error_raw.starts_with("  [cause]: TypeError: inner"));
+    let root = cause.cause.as_ref().unwrap();
+    assert_eq!(root.error_message, "RangeError: root");
+    assert_eq!(root.stacks[0].line, 3);
+    assert_eq!(es.all_stacks().len(), 3);
+  }
+
+  #[test]
+  fn parse_aggregate_error() {
+    let raw = "AggregateError: All promises were rejected\n    at main (https://x/app.js:9:1) {\n  [errors]: [\n    Error: a\n        at fa (https://x/app.js:1:1),\n    Error: b\n        at fb (https://x/app.js:2:1) {\n      [cause]: Error: c\n          at fc (https://x/app.js:3:1)\n    }\n  ]\n}";
+    let es = ErrorStack::from_raw(raw);
+    assert_eq!(es.stacks.len(), 1);
+    let messages: Vec<_> = es.errors.iter().map(|e| e.error_message.as_str()).collect();
+    assert_eq!(messages, vec!["Error: a", "Error: b"]);
+    assert_eq!(es.errors[0].stacks[0].name, "fa");
+    let nested = es.errors[1].cause.as_ref().unwrap();
+    assert_eq!(nested.error_message, "Error: c");
+    assert_eq!(nested.stacks[0].name, "fc");
+  }
+
+  #[test]
+  fn parse_safari_jsc_frames() {
+    let trace = "global code@https://a/app.js:5:10\neval code\neval@[native code]\n[native code]\nmodule code@https://a/mod.js:1:1\nfoo@https://a/app.js:12\nhttps://a/app.js:7:3";
+    let stacks = parse_stack_trace(trace);
+    assert_eq!(stacks.len(), 7);
+    assert_eq!(stacks[0].name, "global code");
+    assert_eq!((stacks[0].line, stacks[0].column), (5, 10));
+    assert!(stacks[0].is_mappable());
+    assert!(stacks[1].is_eval && !stacks[1].is_mappable());
+    assert!(stacks[2].is_native);
+    assert_eq!(stacks[2].name, "eval");
+    assert!(stacks[3].is_native && stacks[3].name.is_empty());
+    assert_eq!(stacks[4].source_file, "https://a/mod.js");
+    assert_eq!(stacks[5].source_file, "https://a/app.js");
+    assert_eq!((stacks[5].line, stacks[5].column), (12, 0));
+    assert_eq!((stacks[6].line, stacks[6].column), (7, 3));
+  }
+
+  #[test]
+  fn parse_v8_eval_frames() {
+    let st = parse_stack_line("at eval (eval at compile (https://x/app.js:10:5), <anonymous>:3:4)")
+      .unwrap();
+    assert!(st.is_eval && st.is_mappable());
+    assert_eq!(st.name, "eval");
+    assert_eq!(
+      (st.source_file, st.line, st.column),
+      ("https://x/app.js", 10, 5)
+    );
+    let origin = st.eval_origin.as_ref().unwrap();
+    assert_eq!(origin.function_name, "compile");
+    assert_eq!((origin.eval_line, origin.eval_column), (3, 4));
+    assert_eq!(origin.source_url, None);
+
+    let nested = parse_stack_line(
+      "at Object.eval (eval at <anonymous> (eval at run (https://x/app.js:7:1)), <anonymous>:1:2)",
+    )
+    .unwrap();
+    assert_eq!(nested.name, "Object.eval");
+    assert_eq!(
+      (nested.source_file, nested.line, nested.column),
+      ("https://x/app.js", 7, 1)
+    );
+    assert_eq!(nested.eval_origin.unwrap().function_name, "run");
+  }
+
+  #[test]
+  fn parse_v8_eval_frame_with_source_url() {
+    let st =
+      parse_stack_line("at render (eval at load (https://x/app.js:2:9), tpl.js:4:6)").unwrap();
+    assert_eq!((st.source_file, st.line, st.column), ("tpl.js", 4, 6));
+    let origin = st.eval_origin.unwrap();
+    assert_eq!(origin.source_url.as_deref(), Some("tpl.js"));
+    assert_eq!(
+      (origin.source_file.as_str(), origin.line, origin.column),
+      ("https://x/app.js", 2, 9)
+    );
+  }
+
+  #[test]
+  fn parse_structured_v8_frames() {
+    let ctor = parse_stack_line("at new Widget (https://x/app.js:3:9)").unwrap();
+    assert!(ctor.is_constructor);
+    assert_eq!(ctor.name, "Widget");
+
+    let alias = parse_stack_line("at async Foo.bar [as baz] (https://x/app.js:5:1)").unwrap();
+    assert!(alias.is_async && !alias.is_constructor);
+    assert_eq!(alias.name, "Foo.bar");
+    assert_eq!(
+      (alias.type_name, alias.method_name, alias.method_alias),
+      (Some("Foo"), Some("bar"), Some("baz"))
+    );
+
+    let anon_async = parse_stack_line("at async https://x/app.js:8:2").unwrap();
+    assert!(anon_async.is_async);
+    assert_eq!(anon_async.source_file, "https://x/app.js");
+
+    let firefox = parse_stack_line("async*load@https://x/app.js:9:4").unwrap();
+    assert!(firefox.is_async);
+    assert_eq!(firefox.name, "load");
+  }
+
+  #[test]
+  fn parse_v8_frames_without_position() {
+    let native = parse_stack_line("at Array.forEach (<anonymous>)").unwrap();
+    assert!(native.is_native && !native.is_mappable());
+    assert_eq!(native.method_name, Some("forEach"));
+    assert!(
+      parse_stack_line("at JSON.parse (native)")
+        .unwrap()
+        .is_native
+    );
+
+    let all = parse_stack_line("at async Promise.all (index 2)").unwrap();
+    assert!(all.is_async && !all.is_native);
+    assert_eq!(all.promise_index, Some(2));
+    assert_eq!(all.unmappable_reason(), Some(FrameFailure::NoPosition));
+
+    // new Function 产生的代码带位置, 不是内置函数
+    let dynamic = parse_stack_line("at foo (<anonymous>:1:5)").unwrap();
+    assert!(!dynamic.is_native);
+  }
+
+  #[test]
+  fn parse_wasm_frames() {
+    let v8 = parse_stack_line("at foo (https://x/app.wasm:wasm-function[42]:0x1a3f)").unwrap();
+    assert_eq!(
+      (v8.name, v8.source_file, v8.line, v8.column),
+      ("foo", "https://x/app.wasm", 1, 0x1a3f)
+    );
+    assert_eq!(v8.wasm_function, Some(42));
+    let anon = parse_stack_line("at wasm://wasm/8b4cbe5a:wasm-function[3]:0x10").unwrap();
+    assert_eq!(
+      (anon.source_file, anon.column),
+      ("wasm://wasm/8b4cbe5a", 16)
+    );
+
+    let firefox = parse_stack_line_as(
+      "foo@https://x/app.wasm:wasm-function[42]:0x1a3f",
+      StackEngine::Firefox,
+    )
+    .unwrap();
+    assert_eq!((firefox.name, firefox.column), ("foo", 0x1a3f));
+    let bare = parse_stack_line("wasm-function[7]:0xff").unwrap();
+    assert_eq!((bare.source_file, bare.wasm_function), ("", Some(7)));
+
+    let safari = parse_stack_line("<?>.wasm-function[42]@[wasm code]").unwrap();
+    assert_eq!(safari.wasm_function, Some(42));
+    assert_eq!(safari.unmappable_reason(), Some(FrameFailure::NoPosition));
+  }
+
+  #[test]
+  fn parse_with_explicit_engine() {
+    let firefox = "load@https://x/app.js:3:7";
+    assert!(parse_stack_line_as(firefox, StackEngine::V8).is_none());
+    assert_eq!(
+      parse_stack_line_as(firefox, StackEngine::Firefox)
+        .unwrap()
+        .name,
+      "load"
+    );
+    // Safari 无名帧不会被 Firefox 解析器误读
+    assert!(parse_stack_line_as("https://x/app.js:7:3", StackEngine::Firefox).is_none());
+    let safari = parse_stack_line_as("https://x/app.js:7:3", StackEngine::Safari).unwrap();
+    assert_eq!((safari.source_file, safari.line), ("https://x/app.js", 7));
+    // 无名且无列号的帧只在显式 Safari 下识别
+    let bare = parse_stack_line_as("https://x/app.js:7", StackEngine::Safari).unwrap();
+    assert_eq!((bare.line, bare.column), (7, 0));
+
+    let hermes = parse_stack_line_as(
+      "at render (address at index.android.bundle:1:2345)",
+      StackEngine::Hermes,
+    )
+    .unwrap();
+    assert_eq!(
+      (hermes.name, hermes.source_file, hermes.column),
+      ("render", "index.android.bundle", 2345)
+    );
+    assert_eq!(
+      parse_stack_trace_as(
+        "at a (https://x/a.js:1:1)\nb@https://x/a.js:2:2",
+        StackEngine::V8
+      )
+      .len(),
+      1
+    );
+    // 显式引擎下 Firefox 帧行归入消息, cause 仍照常拆分
+    let es = ErrorStack::from_raw_as(
+      "Error: x\nb@https://x/a.js:2:2\n    at a (https://x/a.js:1:1)\nCaused by: Error: y\n    at c (https://x/a.js:3:3)",
+      StackEngine::V8,
+    );
+    assert_eq!(es.error_message, "Error: x\nb@https://x/a.js:2:2");
+    assert_eq!(es.stacks.len(), 1);
+    assert_eq!(es.cause.unwrap().stacks[0].name, "c");
+  }
+
+  #[test]
+  fn detect_stack_engine() {
+    assert_eq!(
+      detect_engine("Error: x\n    at a (https://x/a.js:1:1)"),
+      Some(StackEngine::V8)
+    );
+    assert_eq!(
+      detect_engine("Error: x\n    at a (file:///srv/a.js:1:1)\n    at node:internal/main:5:3"),
+      Some(StackEngine::Node)
+    );
+    assert_eq!(
+      detect_engine("a@https://x/a.js:1:1\n@https://x/a.js:2:2"),
+      Some(StackEngine::Firefox)
+    );
+    assert_eq!(
+      detect_engine("a@https://x/a.js:1:1\nglobal code@https://x/a.js:2:2"),
+      Some(StackEngine::Safari)
+    );
+    assert_eq!(
+      detect_engine("at a (address at index.android.bundle:1:9)\nat b (native)"),
+      Some(StackEngine::Hermes)
+    );
+    assert_eq!(detect_engine("no frames here"), None);
+  }
+
   #[test]
   fn parse_stack_line_rejects_non_stack() {
     assert!(parse_stack_line("Just a message without coords").is_none());
@@ -1,12 +1,25 @@
 use serde::Serialize;
 use sourcemap::SourceMap;
 
+use crate::code_frame::{render_code_frame, CodeFrameLine, CodeFrameOptions};
+use crate::context_window::{
+  context_window, mapping_span, truncate_line, ContextOptions, HighlightSpan, LineNumbering,
+};
+use crate::decode_sourcemap;
+use crate::generated_code::{generated_excerpt, GeneratedExcerpt};
+use crate::mapping_index::MappingIndex;
+
 #[derive(Serialize, Clone, Debug)]
 pub struct SourceMapToken {
   pub line: u32,
   pub column: u32,
   pub source_code: Option<String>,
   pub src: Option<String>,
+  /// 原始标识符 (source map `names` 中的条目)，未记录时为 None
+  pub name: Option<String>,
+  /// 命中 token 与查询列的列距, token 不在查询行时为 None
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub column_distance: Option<u32>,
 }
 
 pub fn generate_source_map_token(
@@ -14,7 +27,7 @@ pub fn generate_source_map_token(
   line: u32,
   column: u32,
 ) -> Option<SourceMapToken> {
-  let source_map = SourceMap::from_reader(source_map_content.as_bytes()).ok()?;
+  let source_map = decode_sourcemap(source_map_content.as_bytes()).ok()?;
   generate_source_map_token_from_map(&source_map, line, column)
 }
 
@@ -26,62 +39,74 @@ pub fn generate_source_map_token_from_map(
   if line == 0 {
     return None;
   }
-  if let Some(token) = source_map.lookup_token(line - 1, column) {
-    Some(SourceMapToken {
-      line: token.get_src_line() + 1,
-      column: token.get_src_col() as u32,
-      source_code: token.get_source_view().map(|v| v.source().to_string()),
-      src: token.get_source().map(|s| s.to_string()),
-    })
-  } else {
-    None
+  source_map
+    .lookup_token(line - 1, column)
+    .map(|token| source_map_token(&token, line, column))
+}
+
+/// 查询位置 (1-based 行, 列) 与命中 token 的列距, 不在同一行时为 None
+pub(crate) fn column_distance(token: &sourcemap::Token<'_>, line: u32, column: u32) -> Option<u32> {
+  (token.get_dst_line() + 1 == line).then(|| token.get_dst_col().abs_diff(column))
+}
+
+/// sourcemap token -> [`SourceMapToken`], `line` / `column` 为查询的编译后位置
+pub(crate) fn source_map_token(
+  token: &sourcemap::Token<'_>,
+  line: u32,
+  column: u32,
+) -> SourceMapToken {
+  SourceMapToken {
+    line: token.get_src_line() + 1,
+    column: token.get_src_col(),
+    source_code: token.get_source_view().map(|v| v.source().to_string()),
+    src: token.get_source().map(|s| s.to_string()),
+    name: token.get_name().map(|s| s.to_string()),
+    column_distance: column_distance(token, line, column),
   }
 }
 
+/// 解析 source map 内容并定位单点, `offset_line` 为目标行前后各取的上下文行数 (缺省只取目标行)
+///
+/// 与 [`generate_context_token_from_map`] 不同, 缺少 sourcesContent 时仍返回 token, `source_code` 为空
 pub fn get_stack_source(
   source_map_content: &str,
   line: u32,
   column: u32,
   offset_line: Option<u32>,
 ) -> Option<Token> {
-  let source_token = generate_source_map_token(source_map_content, line, column)?;
-  let mut token = Token {
-    line: source_token.line,
-    column: source_token.column,
-    source_code: Vec::new(),
-    src: source_token.src.clone().unwrap_or_default(),
-  };
-  if let Some(source_code_text) = source_token.source_code {
-    if let Some(offset) = offset_line {
-      let end_line = source_token.line + offset;
-      let start_line = if source_token.line < offset {
-        0
-      } else {
-        source_token.line - offset
-      };
-      for line_number in start_line..end_line {
-        let is_stack_line = line_number == source_token.line;
-        let raw = source_code_text
-          .lines()
-          .nth(line_number as usize)
-          .unwrap_or("")
-          .to_string();
-        token.source_code.push(SourceCode {
-          line: line_number,
-          raw,
-          is_stack_line,
-        });
-      }
-    } else {
-      token.source_code.push(SourceCode {
-        line: source_token.line,
-        raw: source_code_text
-          .lines()
-          .nth(source_token.line as usize)
-          .unwrap_or("")
-          .to_string(),
-        is_stack_line: true,
-      });
+  get_stack_source_with_numbering(
+    source_map_content,
+    line,
+    column,
+    offset_line,
+    LineNumbering::OneBased,
+  )
+}
+
+/// 同 [`get_stack_source`], [`LineNumbering::Legacy`] 下 `source_code` 的行号为 0-based (`Token.line` 不变)
+pub fn get_stack_source_with_numbering(
+  source_map_content: &str,
+  line: u32,
+  column: u32,
+  offset_line: Option<u32>,
+  numbering: LineNumbering,
+) -> Option<Token> {
+  if line == 0 {
+    return None;
+  }
+  let sm = decode_sourcemap(source_map_content.as_bytes()).ok()?;
+  let sm_token = sm.lookup_token(line - 1, column)?;
+  let mut token = context_token(
+    &MappingIndex::new(&sm),
+    &sm_token,
+    line,
+    column,
+    offset_line.unwrap_or(0).into(),
+    LineNumbering::OneBased,
+  );
+  if numbering == LineNumbering::Legacy {
+    for code in &mut token.source_code {
+      code.line -= 1;
     }
   }
   Some(token)
@@ -97,81 +122,94 @@ pub struct GenerateTask {
 
 #[derive(Clone, Debug, Serialize)]
 pub struct SourceCode {
+  /// 1-based 行号
   pub line: u32,
   pub is_stack_line: bool,
   pub raw: String,
+  /// 同 [`crate::context_lookup::ContextLine::column_offset`]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub column_offset: Option<u32>,
 }
 
 #[derive(Clone, Debug, Serialize)]
 pub struct Token {
+  /// 原始 (1-based) 行号, [`LineNumbering::Legacy`] 下 `lookup_token_with_context` 返回 0-based
   pub line: u32,
   pub column: u32,
   pub source_code: Vec<SourceCode>,
   pub src: String,
+  /// 同 [`SourceMapToken::name`]
+  pub name: Option<String>,
+  /// 同 [`SourceMapToken::column_distance`]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub column_distance: Option<u32>,
+  /// 目标行上映射表达式的范围, 缺少 sourcesContent 时为 None
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub span: Option<HighlightSpan>,
+  /// 缺少 sourcesContent 时回退的编译后代码摘录 (`source_code` 为空), 需提供编译后代码
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub generated: Option<GeneratedExcerpt>,
+}
+
+impl Token {
+  /// 将上下文渲染为带行号栏与高亮下划线的代码帧, 回退到编译后代码时渲染其摘录
+  pub fn code_frame(&self, options: CodeFrameOptions) -> String {
+    if let (true, Some(generated)) = (self.source_code.is_empty(), &self.generated) {
+      return generated.code_frame(options);
+    }
+    render_code_frame(
+      self.source_code.iter().map(|c| CodeFrameLine {
+        line: c.line,
+        code: &c.raw,
+        is_target: c.is_stack_line,
+      }),
+      self.span,
+      options,
+    )
+  }
 }
 
 pub struct GenerateToken {
   tokens: Vec<Token>,
   tasks: Vec<GenerateTask>,
+  line_numbering: LineNumbering,
+}
+impl Default for GenerateToken {
+  fn default() -> Self {
+    Self::new()
+  }
 }
+
 impl GenerateToken {
   pub fn new() -> Self {
     GenerateToken {
       tokens: Vec::new(),
       tasks: Vec::new(),
+      line_numbering: LineNumbering::OneBased,
     }
   }
+
+  /// 设置输出的行号约定, 同 [`get_stack_source_with_numbering`]
+  pub fn with_line_numbering(mut self, numbering: LineNumbering) -> Self {
+    self.line_numbering = numbering;
+    self
+  }
+
   pub fn add_task(&mut self, task: GenerateTask) {
     self.tasks.push(task);
   }
   pub fn generate(&mut self) {
     for task in &self.tasks {
-      if let Some(source_token) =
-        generate_source_map_token(&task.source_map_content, task.line, task.column)
-      {
-        let mut token = Token {
-          line: source_token.line,
-          column: source_token.column,
-          source_code: Vec::new(),
-          src: source_token.src.clone().unwrap_or_default(),
-        };
-        if let Some(source_code_text) = source_token.source_code {
-          match task.source_line_offset {
-            Some(offset) => {
-              let end_line = source_token.line + offset;
-              let start_line = if source_token.line < offset {
-                0
-              } else {
-                source_token.line - offset
-              };
-              for line_number in start_line..end_line {
-                let is_stack_line = line_number == source_token.line;
-                let raw = source_code_text
-                  .lines()
-                  .nth(line_number as usize)
-                  .unwrap_or("")
-                  .to_string();
-                token.source_code.push(SourceCode {
-                  line: line_number,
-                  raw,
-                  is_stack_line,
-                });
-              }
-            }
-            None => {
-              token.source_code.push(SourceCode {
-                line: source_token.line,
-                raw: source_code_text
-                  .lines()
-                  .nth(source_token.line as usize)
-                  .unwrap_or("")
-                  .to_string(),
-                is_stack_line: true,
-              });
-            }
-          }
-          self.tokens.push(token);
-        }
+      let token = get_stack_source_with_numbering(
+        &task.source_map_content,
+        task.line,
+        task.column,
+        task.source_line_offset,
+        self.line_numbering,
+      );
+      // 缺少 sourcesContent 的任务不产出 token
+      if let Some(token) = token.filter(|t| !t.source_code.is_empty()) {
+        self.tokens.push(token);
       }
     }
   }
@@ -184,40 +222,126 @@ pub fn generate_context_token_from_map(
   sm: &SourceMap,
   line: u32,
   column: u32,
-  context_lines: u32,
+  context: impl Into<ContextOptions>,
+) -> Option<Token> {
+  generate_context_token_from_map_with_numbering(sm, line, column, context, LineNumbering::OneBased)
+}
+
+/// 同 [`generate_context_token_from_map`], 按 `numbering` 输出 `Token.line`
+pub fn generate_context_token_from_map_with_numbering(
+  sm: &SourceMap,
+  line: u32,
+  column: u32,
+  context: impl Into<ContextOptions>,
+  numbering: LineNumbering,
+) -> Option<Token> {
+  if line == 0 {
+    return None;
+  }
+  let sm_token = sm.lookup_token(line - 1, column)?;
+  sm_token.get_source_view()?;
+  Some(context_token(
+    &MappingIndex::new(sm),
+    &sm_token,
+    line,
+    column,
+    context.into(),
+    numbering,
+  ))
+}
+
+/// 同 [`generate_context_token_from_map`], 缺少 sourcesContent 时不返回 None, 而是以编译后代码
+/// `generated_source` 在查询位置附近的摘录 (压缩代码按语句美化) 作为 [`Token::generated`] 回退
+pub fn generate_context_token_with_fallback(
+  sm: &SourceMap,
+  generated_source: &str,
+  line: u32,
+  column: u32,
+  context: impl Into<ContextOptions>,
+) -> Option<Token> {
+  generate_context_token_with_fallback_and_numbering(
+    sm,
+    generated_source,
+    line,
+    column,
+    context,
+    LineNumbering::OneBased,
+  )
+}
+
+/// 同 [`generate_context_token_with_fallback`], 按 `numbering` 输出 `Token.line`
+pub fn generate_context_token_with_fallback_and_numbering(
+  sm: &SourceMap,
+  generated_source: &str,
+  line: u32,
+  column: u32,
+  context: impl Into<ContextOptions>,
+  numbering: LineNumbering,
 ) -> Option<Token> {
   if line == 0 {
     return None;
   }
+  let options = context.into();
   let sm_token = sm.lookup_token(line - 1, column)?;
-  let origin_line = sm_token.get_src_line() as u32;
-  let origin_col = sm_token.get_src_col() as u32;
-  let src_path = sm_token
-    .get_source()
-    .map(|s| s.to_string())
+  let index = MappingIndex::new(sm);
+  let mut token = context_token(&index, &sm_token, line, column, options, numbering);
+  if sm_token.get_source_view().is_none() {
+    let span_end = index.next_generated_column(line - 1, column);
+    token.generated = Some(generated_excerpt(
+      generated_source,
+      line - 1,
+      column,
+      span_end,
+      options,
+    )?);
+  }
+  Some(token)
+}
+
+/// 以 token 的原始位置为中心截取上下文, 缺少 sourcesContent 时 `source_code` 为空
+pub(crate) fn context_token(
+  index: &MappingIndex,
+  sm_token: &sourcemap::Token<'_>,
+  line: u32,
+  column: u32,
+  options: ContextOptions,
+  numbering: LineNumbering,
+) -> Token {
+  let (origin_line, origin_column) = (sm_token.get_src_line(), sm_token.get_src_col());
+  let mut target_kept = None;
+  let source_code = sm_token
+    .get_source_view()
+    .map(|view| {
+      context_window(view.source(), origin_line, options.before, options.after)
+        .into_iter()
+        .map(|l| {
+          let (code, kept) = truncate_line(l.code, origin_column, options.max_line_width);
+          if l.is_target {
+            target_kept = kept;
+          }
+          SourceCode {
+            line: l.line + 1,
+            is_stack_line: l.is_target,
+            raw: code.into_owned(),
+            column_offset: kept.map(|(from, _)| from),
+          }
+        })
+        .collect()
+    })
     .unwrap_or_default();
-  let mut token = Token {
-    line: origin_line,
-    column: origin_col,
-    source_code: Vec::new(),
-    src: src_path,
-  };
-  if let Some(view) = sm_token.get_source_view() {
-    let source_text = view.source();
-    let lines: Vec<&str> = source_text.lines().collect();
-    let start = origin_line.saturating_sub(context_lines);
-    let end = origin_line + context_lines;
-    for ln in start..=end {
-      let raw = lines.get(ln as usize).cloned().unwrap_or("").to_string();
-      token.source_code.push(SourceCode {
-        line: ln + 1,
-        is_stack_line: ln == origin_line,
-        raw,
-      });
-    }
-    return Some(token);
+  Token {
+    line: numbering.number(origin_line),
+    column: sm_token.get_src_col(),
+    source_code,
+    src: sm_token
+      .get_source()
+      .map(|s| s.to_string())
+      .unwrap_or_default(),
+    name: sm_token.get_name().map(|s| s.to_string()),
+    column_distance: column_distance(sm_token, line, column),
+    span: mapping_span(index, sm_token).map(|s| s.within(target_kept)),
+    generated: None,
   }
-  None
 }
 
 #[cfg(test)]
@@ -242,8 +366,30 @@ mod tests {
   fn test_get_stack_source_with_offset() {
     let sm = simple_sm("a.js", "l0()\\nl1()\\nl2()\\n");
     let tok = get_stack_source(&sm, 1, 0, Some(1)).expect("token");
-    // offset=1 应至少包含 1 行(目标) + 1 行上/下文(如果存在)
-    assert!(tok.source_code.len() >= 1);
+    // 目标行为第 1 行, 之前无上文, 之后取 1 行
+    let lines: Vec<_> = tok
+      .source_code
+      .iter()
+      .map(|c| (c.line, c.raw.as_str(), c.is_stack_line))
+      .collect();
+    assert_eq!(lines, vec![(1, "l0()", true), (2, "l1()", false)]);
+  }
+
+  #[test]
+  fn test_get_stack_source_indexed_map() {
+    let sm = r#"{"version":3,"sections":[
+      {"offset":{"line":0,"column":0},"map":{"version":3,"sources":["a.js"],"sourcesContent":["a()\n"],"names":[],"mappings":"AAAA"}},
+      {"offset":{"line":1,"column":0},"map":{"version":3,"sources":["b.js"],"sourcesContent":["x()\ny()\n"],"names":[],"mappings":";AACA"}}
+    ]}"#;
+    let tok = get_stack_source(sm, 3, 0, Some(1)).expect("token in second section");
+    assert_eq!(tok.src, "b.js");
+    assert_eq!(tok.line, 2);
+    assert!(tok
+      .source_code
+      .iter()
+      .any(|c| c.is_stack_line && c.raw == "y()"));
+    let single = generate_source_map_token(sm, 1, 0).expect("token in first section");
+    assert_eq!(single.src.as_deref(), Some("a.js"));
   }
 
   #[test]
@@ -267,11 +413,62 @@ mod tests {
     assert_eq!(tokens.len(), 2);
   }
 
+  #[test]
+  fn test_get_stack_source_legacy_numbering() {
+    let sm = simple_sm("a.js", "l0()\\nl1()\\n");
+    let tok = get_stack_source_with_numbering(&sm, 1, 0, Some(1), LineNumbering::Legacy).unwrap();
+    assert_eq!(tok.line, 1);
+    let lines: Vec<_> = tok
+      .source_code
+      .iter()
+      .map(|c| (c.line, c.is_stack_line))
+      .collect();
+    assert_eq!(lines, vec![(0, true), (1, false)]);
+
+    let mut gen = GenerateToken::new().with_line_numbering(LineNumbering::Legacy);
+    gen.add_task(GenerateTask {
+      source_map_content: sm,
+      line: 1,
+      column: 0,
+      source_line_offset: Some(1),
+    });
+    gen.generate();
+    assert_eq!(gen.get_tokens()[0].source_code[1].line, 1);
+  }
+
   #[test]
   fn test_generate_context_token_from_map() {
     let sm_raw = simple_sm("a.js", "l0()\\nl1()\\nl2()\\n");
     let sm = SourceMap::from_reader(sm_raw.as_bytes()).unwrap();
     let tok = generate_context_token_from_map(&sm, 1, 0, 1).unwrap();
-    assert!(tok.source_code.len() >= 2);
+    let legacy =
+      generate_context_token_from_map_with_numbering(&sm, 1, 0, 1, LineNumbering::Legacy).unwrap();
+    assert_eq!(legacy.line + 1, tok.line);
+    assert_eq!(tok.line, 1);
+    assert_eq!(tok.source_code.len(), 2);
+    assert!(tok.source_code[0].is_stack_line);
+  }
+
+  #[test]
+  fn test_generate_context_token_with_fallback() {
+    let sm = SourceMap::from_reader(
+      br#"{"version":3,"sources":["a.js"],"names":[],"mappings":"AAAA,SAAS,IAAI"}"#.as_slice(),
+    )
+    .unwrap();
+    let generated = "function add(a,b){return a+b}";
+    assert!(generate_context_token_from_map(&sm, 1, 9, 0).is_none());
+    let tok = generate_context_token_with_fallback(&sm, generated, 1, 9, 0).unwrap();
+    assert!(tok.source_code.is_empty());
+    let excerpt = tok.generated.as_ref().unwrap();
+    assert_eq!(excerpt.span, Some(HighlightSpan { start: 9, end: 13 }));
+    assert_eq!(
+      tok.code_frame(CodeFrameOptions::default()),
+      "> 1 | function add(a,b){return a+b}\n    |          ^^^^"
+    );
+    // 有 sourcesContent 时不回退
+    let sm_raw = simple_sm("a.js", "l0()\\n");
+    let sm = SourceMap::from_reader(sm_raw.as_bytes()).unwrap();
+    let tok = generate_context_token_with_fallback(&sm, generated, 1, 0, 0).unwrap();
+    assert!(tok.generated.is_none());
   }
 }
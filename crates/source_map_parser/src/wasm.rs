@@ -0,0 +1,86 @@
+/// wasm 模块魔数 `\0asm`
+const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// 声明 source map 地址的自定义段名称
+const SOURCE_MAPPING_URL_SECTION: &str = "sourceMappingURL";
+
+/// 读取无符号 LEB128 整数, 返回 (值, 占用字节数)
+fn read_leb128_u32(bytes: &[u8]) -> Option<(u32, usize)> {
+  let mut value: u32 = 0;
+  for (i, byte) in bytes.iter().take(5).enumerate() {
+    value |= u32::from(byte & 0x7f) << (7 * i);
+    if byte & 0x80 == 0 {
+      return Some((value, i + 1));
+    }
+  }
+  None
+}
+
+/// 读取 LEB128 长度前缀的 UTF-8 字符串, 返回 (字符串, 占用字节数)
+fn read_name(bytes: &[u8]) -> Option<(&str, usize)> {
+  let (len, n) = read_leb128_u32(bytes)?;
+  let end = n.checked_add(len as usize)?;
+  let name = std::str::from_utf8(bytes.get(n..end)?).ok()?;
+  Some((name, end))
+}
+
+/// 从 wasm 模块二进制中读取 `sourceMappingURL` 自定义段的值
+///
+/// 模块格式不合法或没有该段时返回 None
+pub fn source_mapping_url(module: &[u8]) -> Option<String> {
+  if module.get(..4)? != WASM_MAGIC {
+    return None;
+  }
+  // 跳过魔数与 4 字节版本号
+  let mut pos = 8;
+  while pos < module.len() {
+    let id = module[pos];
+    let (size, n) = read_leb128_u32(&module[pos + 1..])?;
+    let start = pos + 1 + n;
+    let end = start.checked_add(size as usize)?;
+    let section = module.get(start..end)?;
+    if id == 0 {
+      let (name, name_len) = read_name(section)?;
+      if name == SOURCE_MAPPING_URL_SECTION {
+        return read_name(&section[name_len..]).map(|(url, _)| url.to_string());
+      }
+    }
+    pos = end;
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn custom_section(name: &str, payload: &[u8]) -> Vec<u8> {
+    let mut body = vec![name.len() as u8];
+    body.extend_from_slice(name.as_bytes());
+    body.extend_from_slice(payload);
+    let mut section = vec![0u8, body.len() as u8];
+    section.extend(body);
+    section
+  }
+
+  #[test]
+  fn read_source_mapping_url_section() {
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    // type 段 (id 1) 与无关的 name 自定义段
+    module.extend_from_slice(&[1, 1, 0]);
+    module.extend(custom_section("name", &[0]));
+    let url = "app_bg.wasm.map";
+    let mut payload = vec![url.len() as u8];
+    payload.extend_from_slice(url.as_bytes());
+    module.extend(custom_section(SOURCE_MAPPING_URL_SECTION, &payload));
+    assert_eq!(source_mapping_url(&module).as_deref(), Some(url));
+  }
+
+  #[test]
+  fn reject_invalid_modules() {
+    assert_eq!(source_mapping_url(b"not wasm"), None);
+    assert_eq!(source_mapping_url(b"\0asm\x01\0\0\0"), None);
+    // 段长度越界
+    assert_eq!(source_mapping_url(b"\0asm\x01\0\0\0\x00\x7f"), None);
+  }
+}
@@ -1,4 +1,4 @@
-use source_map_parser::SourceMapParserClient;
+use source_map_parser::{source_provider::LocalSourceRoot, SourceMapParserClient};
 use std::{fs, path::PathBuf};
 
 /// 加载仓库根目录 `assets/index.js.map`
@@ -44,9 +44,10 @@ fn map_error_stack_with_context() {
   );
   let mapped = client.map_error_stack(&raw, Some(1));
   assert_eq!(mapped.error_message, "ReferenceError: x");
-  assert!(mapped.frames_with_context.len() >= 1);
-  let ctx = &mapped.frames_with_context[0];
-  assert!(ctx.source_code.len() >= 2); // context lines
+  assert_eq!(mapped.frames.len(), 1);
+  let loc = mapped.frames[0].mapped().expect("frame mapped");
+  let ctx = loc.context.as_ref().expect("context");
+  assert!(ctx.len() >= 2); // context lines
 }
 
 #[test]
@@ -54,7 +55,7 @@ fn unpack_all_sources_multi() {
   let client = load_client();
   let sources = client.unpack_all_sources();
   // 真实 sourcemap 下应至少包含 1 个源文件，且内容非空
-  assert!(sources.len() >= 1);
+  assert!(!sources.is_empty());
   let any_non_empty = sources.values().any(|v| !v.is_empty());
   assert!(any_non_empty);
 }
@@ -82,5 +83,59 @@ fn context_window_edge_at_start() {
   let (line, col) = find_first_mapped_position(&client).expect("find a mapped position");
   let tok = client.lookup_token_with_context(line, col, 5).unwrap();
   // start 行不足 context 也不会 panic，长度 >= 原行 (1) + min(请求, 实际前后存在)
-  assert!(tok.source_code.len() >= 1);
+  assert!(!tok.source_code.is_empty());
+}
+
+#[test]
+fn generated_excerpt_from_bundle() {
+  let mut p = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  p.push("../../assets/index.js");
+  let client = load_client().with_generated_source(fs::read_to_string(&p).unwrap());
+  assert_eq!(client.generated_file(), Some("index-DZaFENjX.js"));
+  let ex = client.lookup_generated_excerpt(9, 58, 1).expect("excerpt");
+  // 第 9 行为压缩代码, 按语句拆行后取目标语句前后各 1 行
+  assert!(ex.beautified);
+  assert_eq!(ex.lines.len(), 3);
+  let target = ex.lines.iter().find(|l| l.is_target).unwrap();
+  let span = ex.span.unwrap();
+  // 高亮从查询列到同一行的下一个映射
+  assert!(target.code.starts_with("  var D=Symbol.for("));
+  assert_eq!(&target.code[span.start as usize..span.end as usize], "for(");
+
+  let stack = "Error: x\n    at a (https://x/index.js:9:58)";
+  let mapped = client.map_error_stack(stack, Some(0));
+  let generated = mapped.frames[0].mapped().unwrap().generated.as_ref();
+  assert_eq!(generated.unwrap().lines.len(), 1);
+  assert!(load_client().map_error_stack(stack, Some(0)).frames[0]
+    .mapped()
+    .unwrap()
+    .generated
+    .is_none());
+}
+
+#[test]
+fn source_root_restores_stripped_sources_content() {
+  let full = load_client();
+  let mut map: serde_json::Value = serde_json::from_slice(&load_sourcemap_bytes()).unwrap();
+  map.as_object_mut().unwrap().remove("sourcesContent");
+  let stripped = serde_json::to_vec(&map).unwrap();
+  let client = SourceMapParserClient::new(&stripped).unwrap();
+  assert!(client.lookup_context(9, 59, 1).is_none());
+
+  // sources 形如 ../../node_modules/..., 相对 <tmp>/dist/assets 解析写回
+  let tmp = tempfile::tempdir().unwrap();
+  let root = LocalSourceRoot::new(tmp.path()).with_base_dir("dist/assets");
+  let src = full.lookup_context(9, 59, 1).expect("context").src;
+  let path = root.resolve(&src).unwrap();
+  fs::create_dir_all(path.parent().unwrap()).unwrap();
+  fs::write(&path, &full.unpack_all_sources()[&src]).unwrap();
+
+  let client = client.with_source_provider(&root);
+  let restored = client.lookup_context(9, 59, 1).expect("restored context");
+  let expected = full.lookup_context(9, 59, 1).unwrap();
+  let codes = |s: &source_map_parser::context_lookup::ContextSnippet| {
+    s.context.iter().map(|l| l.code.clone()).collect::<Vec<_>>()
+  };
+  assert_eq!(codes(&restored), codes(&expected));
+  assert_eq!(restored.span, expected.span);
 }